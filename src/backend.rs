@@ -0,0 +1,285 @@
+use std::fmt;
+use std::io;
+
+use crate::Style;
+
+/// Drawing surface used by `Render`
+///
+/// Coordinates are terminal cells with the origin in the top left corner.
+pub trait Backend {
+    /// Returns `(columns, rows)` of the surface
+    fn size(&self) -> io::Result<(u16, u16)>;
+    fn move_cursor(&mut self, x: u16, y: u16) -> io::Result<()>;
+    /// Writes at the cursor and advances it past the written characters
+    fn write_str(&mut self, s: &str) -> io::Result<()>;
+    /// Sets the style used by following writes
+    fn set_style(&mut self, style: Style) -> io::Result<()>;
+    fn clear(&mut self) -> io::Result<()>;
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Whether colors can be shown, `Render` falls back to monochrome otherwise
+    fn supports_color(&self) -> bool {
+        true
+    }
+}
+
+/// Backend keeping a grid of characters in memory, used to assert rendered frames in tests
+pub struct MemoryBackend {
+    columns: u16,
+    rows: u16,
+    cells: Vec<char>,
+    styles: Vec<Style>,
+    cursor: (u16, u16),
+    current_style: Style,
+    pub color: bool,
+    pub flushes: usize,
+    /// Number of characters written since creation, to assert how much a frame redrew
+    pub cells_written: usize,
+}
+
+impl MemoryBackend {
+    pub fn new(columns: u16, rows: u16) -> Self {
+        Self {
+            columns,
+            rows,
+            cells: vec![' '; columns as usize * rows as usize],
+            styles: vec![Style::default(); columns as usize * rows as usize],
+            cursor: (0, 0),
+            current_style: Style::default(),
+            color: true,
+            flushes: 0,
+            cells_written: 0,
+        }
+    }
+
+    pub fn cell(&self, x: u16, y: u16) -> Option<char> {
+        if x < self.columns && y < self.rows {
+            Some(self.cells[y as usize * self.columns as usize + x as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn style(&self, x: u16, y: u16) -> Option<Style> {
+        if x < self.columns && y < self.rows {
+            Some(self.styles[y as usize * self.columns as usize + x as usize])
+        } else {
+            None
+        }
+    }
+
+    pub fn line(&self, y: u16) -> String {
+        let start = y as usize * self.columns as usize;
+        self.cells[start..start + self.columns as usize]
+            .iter()
+            .collect()
+    }
+
+    /// Returns the frame with trailing spaces trimmed from every line
+    pub fn lines(&self) -> Vec<String> {
+        (0..self.rows)
+            .map(|y| self.line(y).trim_end().to_string())
+            .collect()
+    }
+}
+
+impl fmt::Display for MemoryBackend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for line in self.lines() {
+            writeln!(f, "{}", line)?;
+        }
+        Ok(())
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn size(&self) -> io::Result<(u16, u16)> {
+        Ok((self.columns, self.rows))
+    }
+
+    fn move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+        self.cursor = (x, y);
+        Ok(())
+    }
+
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        let (mut x, y) = self.cursor;
+        for ch in s.chars() {
+            if x < self.columns && y < self.rows {
+                let i = y as usize * self.columns as usize + x as usize;
+                self.cells[i] = ch;
+                self.styles[i] = self.current_style;
+            }
+            x = x.saturating_add(1);
+            self.cells_written += 1;
+        }
+        self.cursor = (x, y);
+        Ok(())
+    }
+
+    fn set_style(&mut self, style: Style) -> io::Result<()> {
+        self.current_style = style;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.cells.fill(' ');
+        self.styles.fill(Style::default());
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+
+    fn supports_color(&self) -> bool {
+        self.color
+    }
+}
+
+#[cfg(feature = "terminal")]
+pub use self::terminal::{CrosstermBackend, restore_terminal};
+
+#[cfg(feature = "terminal")]
+mod terminal {
+    use std::io::{self, Stdout, Write, stdout};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use crossterm::{
+        ExecutableCommand, cursor,
+        event::{
+            DisableMouseCapture, EnableMouseCapture, KeyboardEnhancementFlags,
+            PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags,
+        },
+        queue,
+        style::{
+            self, Attribute, ResetColor, SetAttribute, SetBackgroundColor, SetForegroundColor,
+        },
+        terminal::{self, Clear, ClearType},
+    };
+
+    use super::Backend;
+    use crate::{Color, Style};
+
+    fn to_crossterm(color: Color) -> style::Color {
+        match color {
+            Color::Default => style::Color::Reset,
+            Color::Red => style::Color::Red,
+            Color::Green => style::Color::Green,
+            Color::Yellow => style::Color::Yellow,
+            Color::Blue => style::Color::Blue,
+            Color::Magenta => style::Color::Magenta,
+            Color::Cyan => style::Color::Cyan,
+            Color::White => style::Color::White,
+            Color::DarkGrey => style::Color::DarkGrey,
+        }
+    }
+
+    /// Set while raw mode is on, so a panic or signal anywhere can undo it exactly once
+    static RAW_MODE: AtomicBool = AtomicBool::new(false);
+    static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+
+    /// Leaves raw mode and undoes everything the game changed in the terminal. Does
+    /// nothing when raw mode is already off, so every exit path may call it.
+    pub fn restore_terminal() -> io::Result<()> {
+        if !RAW_MODE.swap(false, Ordering::SeqCst) {
+            return Ok(());
+        }
+        let mut stdout = stdout();
+        if KEYBOARD_ENHANCED.swap(false, Ordering::SeqCst) {
+            let _ = stdout.execute(PopKeyboardEnhancementFlags);
+        }
+        // Harmless when the mouse was never captured
+        let _ = stdout.execute(DisableMouseCapture);
+        stdout.execute(ResetColor)?;
+        stdout.execute(Clear(ClearType::All))?;
+        stdout.execute(cursor::Show)?;
+        terminal::disable_raw_mode()?;
+        stdout.flush()?;
+        Ok(())
+    }
+
+    /// Backend queueing crossterm commands on stdout
+    pub struct CrosstermBackend {
+        pub stdout: Stdout,
+    }
+
+    impl CrosstermBackend {
+        pub fn new() -> Self {
+            Self { stdout: stdout() }
+        }
+
+        /// Enables raw mode and, when supported, key release reporting.
+        /// Returns whether keyboard enhancement flags were pushed.
+        pub fn terminal_raw_mode(&mut self) -> io::Result<bool> {
+            terminal::enable_raw_mode()?;
+            RAW_MODE.store(true, Ordering::SeqCst);
+            let kb_flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                | KeyboardEnhancementFlags::REPORT_EVENT_TYPES;
+            let kb_enhanced = self
+                .stdout
+                .execute(PushKeyboardEnhancementFlags(kb_flags))
+                .is_ok();
+            KEYBOARD_ENHANCED.store(kb_enhanced, Ordering::SeqCst);
+            queue!(self.stdout, cursor::Hide)?;
+
+            Ok(kb_enhanced)
+        }
+
+        /// Makes the terminal report mouse moves and clicks
+        pub fn capture_mouse(&mut self) -> io::Result<()> {
+            self.stdout.execute(EnableMouseCapture)?;
+            Ok(())
+        }
+    }
+
+    impl Default for CrosstermBackend {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Backend for CrosstermBackend {
+        fn size(&self) -> io::Result<(u16, u16)> {
+            terminal::size()
+        }
+
+        fn move_cursor(&mut self, x: u16, y: u16) -> io::Result<()> {
+            queue!(self.stdout, cursor::MoveTo(x, y))
+        }
+
+        fn write_str(&mut self, s: &str) -> io::Result<()> {
+            self.stdout.write_all(s.as_bytes())
+        }
+
+        fn set_style(&mut self, style: Style) -> io::Result<()> {
+            queue!(
+                self.stdout,
+                SetAttribute(Attribute::Reset),
+                SetForegroundColor(to_crossterm(style.fg)),
+                SetBackgroundColor(to_crossterm(style.bg))
+            )?;
+            if style.bold {
+                queue!(self.stdout, SetAttribute(Attribute::Bold))?;
+            }
+            if style.reverse {
+                queue!(self.stdout, SetAttribute(Attribute::Reverse))?;
+            }
+            Ok(())
+        }
+
+        fn clear(&mut self) -> io::Result<()> {
+            queue!(self.stdout, Clear(ClearType::All))
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.stdout.flush()
+        }
+
+        /// Honors `NO_COLOR` and the color count crossterm derives from `TERM`/`COLORTERM`
+        fn supports_color(&self) -> bool {
+            std::env::var_os("NO_COLOR").is_none() && style::available_color_count() >= 8
+        }
+    }
+}
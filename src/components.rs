@@ -1,5 +1,8 @@
+use hecs::Entity;
 use serde::{Deserialize, Serialize};
 
+use crate::Sprite;
+
 pub enum Direction {
     Right,
     Left,
@@ -12,25 +15,93 @@ pub struct Position {
     pub y: u16,
 }
 
-pub struct PrevPosition {
-    pub x: u16,
-    pub y: u16,
-}
-
 pub struct Velocity {
     pub speed: f32,
     pub move_accumulator: f32,
     pub direction: Direction,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Color {
+    /// Terminal's own foreground or background color
+    #[default]
+    Default,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    DarkGrey,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub reverse: bool,
+}
+
+impl Style {
+    pub const fn fg(fg: Color) -> Self {
+        Style {
+            fg,
+            bg: Color::Default,
+            bold: false,
+            reverse: false,
+        }
+    }
+
+    pub const fn bold(self) -> Self {
+        Style { bold: true, ..self }
+    }
+
+    pub const fn reverse(self) -> Self {
+        Style {
+            reverse: true,
+            ..self
+        }
+    }
+
+    /// Drops the colors and keeps the attributes, for terminals without color support
+    pub const fn monochrome(self) -> Self {
+        Style {
+            fg: Color::Default,
+            bg: Color::Default,
+            ..self
+        }
+    }
+}
+
 pub struct Renderable {
-    pub sprite_top: &'static str,
-    pub sprite_bottom: &'static str,
+    pub sprite_top: Sprite,
+    pub sprite_bottom: Sprite,
+    /// Cells covered, both sprites are this wide or empty
     pub width: u16,
-    pub destroy: bool,
-    pub erased: bool,
+    pub style: Style,
+}
+
+/// Kinds of short animations played where something was destroyed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EffectKind {
+    EnemyExplosion,
+    PlayerExplosion,
+    /// Two projectiles shot each other down
+    ShotBurst,
 }
 
+/// Plays the frames of its kind and despawns itself after the last one
+pub struct Effect {
+    pub kind: EffectKind,
+    /// Seconds since the effect was spawned
+    pub elapsed: f32,
+}
+
+/// Marks an entity to be despawned by `entity_cleanup` at the end of the tick
+pub struct Despawn;
+
 pub struct ProjectileSpawner {
     pub probability: f64,
     pub projectile_speed: f32,
@@ -44,12 +115,235 @@ pub struct PlayerProjectile;
 
 pub struct CoPlayerProjectile;
 
+/// Targets a piercing shot already went through, each is only hit once
+#[derive(Debug, Clone, Default)]
+pub struct Pierced {
+    pub targets: Vec<Entity>,
+}
+
 pub struct Enemy;
 
 pub struct EnemyProjectile;
 
+/// Invader type, decides sprite, score value, hit points and firing pattern
+/// through `EnemyKind::stats`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnemyKind {
+    /// Back row, fires shots aimed at the nearest cannon
+    Squid,
+    /// Armored middle row, takes two hits
+    Crab,
+    /// Front row, fires straight down
+    Octopus,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FirePattern {
+    Straight,
+    Aimed,
+}
+
+pub struct Health {
+    pub hp: u8,
+}
+
+/// Sideways movement of a projectile on top of its vertical `Velocity`
+pub struct Drift {
+    pub speed: f32,
+    pub move_accumulator: f32,
+}
+
+/// Effect granted by catching a falling power-up
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PowerUpKind {
+    /// Fire again without waiting for the last shot to land
+    RapidFire,
+    /// Fire three shots fanning out
+    SpreadShot,
+    /// Shots pass through everything they hit
+    Piercing,
+    /// Enemy shots don't cost lives
+    Shield,
+    ExtraLife,
+}
+
+/// Falling power-up drop
+pub struct PowerUp {
+    pub kind: PowerUpKind,
+}
+
+/// Seconds left of every timed power-up of a player or co-player
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PowerUps {
+    pub rapid_fire: f32,
+    pub spread_shot: f32,
+    pub piercing: f32,
+    pub shield: f32,
+    /// Seconds until rapid fire may shoot again
+    pub fire_cooldown: f32,
+}
+
+/// Invader that broke formation and follows a dive path. Its slot keeps moving
+/// with the formation so it can find its way back.
+pub struct Diving {
+    pub slot: Position,
+    /// Where the dive started
+    pub start_x: f32,
+    pub start_y: f32,
+    /// Column of the cannon it dives at
+    pub target_x: f32,
+    /// -1.0 to swing out to the left first, 1.0 for the right
+    pub side: f32,
+    /// Leaves through the bottom and re-enters from the top instead of turning back
+    pub loop_around: bool,
+    /// Seconds since the dive started
+    pub time: f32,
+    pub fire_timer: f32,
+}
+
+/// Step of the boss movement script
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BossPhase {
+    /// Sweeps from wall to wall taking aimed shots
+    Strafe,
+    /// Holds still and fires fans of projectiles
+    Burst,
+    /// Swoops down towards the bunkers and back up
+    Dive,
+}
+
+/// Boss anchor, its `Position` is the top left corner of the part grid
+pub struct Boss {
+    pub hp: u16,
+    pub max_hp: u16,
+    pub phase: BossPhase,
+    /// Index of the current phase in the boss script
+    pub script_step: usize,
+    /// Seconds spent in the current phase
+    pub phase_time: f32,
+    /// Seconds spent strafing in total, keeps the sweep continuous across phases
+    pub strafe_time: f32,
+    pub fire_timer: f32,
+}
+
+/// Piece of a boss drawn and hit separately, placed relative to the anchor
+pub struct BossPart {
+    pub boss: Entity,
+    pub offset_x: u16,
+    pub offset_y: u16,
+    /// Hits on weak points damage the boss, the armor only absorbs shots
+    pub weak_point: bool,
+}
+
+/// Mystery saucer crossing the top row, worth a bonus when shot down
+pub struct Ufo {
+    pub points: i32,
+}
+
+/// Short-lived text drawn at the entity position, e.g. the score awarded for a UFO
+pub struct FloatingText {
+    pub text: String,
+    pub ttl: f32,
+    pub style: Style,
+}
+
+/// Single erodible cell of a defensive bunker, its hit points are kept in `Health`
+pub struct BunkerCell;
+
+// Collision layers, an entity reacts to every layer set in its `BoundingBox::mask`
+pub const LAYER_PLAYER: u8 = 1 << 0;
+pub const LAYER_PLAYER_SHOT: u8 = 1 << 1;
+pub const LAYER_ENEMY: u8 = 1 << 2;
+pub const LAYER_ENEMY_SHOT: u8 = 1 << 3;
+pub const LAYER_UFO: u8 = 1 << 4;
+pub const LAYER_BUNKER: u8 = 1 << 5;
+pub const LAYER_POWER_UP: u8 = 1 << 6;
+pub const LAYER_BOSS: u8 = 1 << 7;
+
+/// Area an entity occupies for collisions, `height` rows from `y_offset` rows below
+/// its `Position` downwards and `width` columns to the right
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub width: u16,
+    pub height: u16,
+    pub y_offset: u16,
+    pub layer: u8,
+    pub mask: u8,
+}
+
+impl BoundingBox {
+    pub fn overlaps(&self, pos: &Position, other: &BoundingBox, other_pos: &Position) -> bool {
+        let top = pos.y as i32 - self.y_offset as i32;
+        let other_top = other_pos.y as i32 - other.y_offset as i32;
+
+        pos.x < other_pos.x + other.width
+            && other_pos.x < pos.x + self.width
+            && top > other_top - other.height as i32
+            && other_top > top - self.height as i32
+    }
+}
+
+/// How an entity reacts when it collides with something in its mask
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Collides {
+    /// Despawned on contact, e.g. projectiles
+    DestroySelf,
+    /// Loses one `Health` point per contact and is despawned at zero
+    LoseHP,
+    /// Costs the players a life, the entity itself stays
+    LoseLife,
+    /// Unaffected by contact, e.g. piercing shots
+    Pierce,
+}
+
+/// Collision resolved by the collision pass this tick, one per reacting entity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CollisionEvent {
+    pub entity: Entity,
+    pub other: Entity,
+    /// Whether the collision despawned `entity`
+    pub destroyed: bool,
+}
+
+// Entity kinds replicated in `NetPacket::GameStateUpdate` as `(code, x, y)`
+pub const NET_ENEMY: u16 = 0;
+pub const NET_ENEMY_PROJECTILE: u16 = 1;
+pub const NET_PLAYER: u16 = 2;
+pub const NET_PLAYER_PROJECTILE: u16 = 3;
+pub const NET_COPLAYER: u16 = 4;
+pub const NET_COPLAYER_PROJECTILE: u16 = 5;
+pub const NET_UFO: u16 = 6;
+pub const NET_ENEMY_CRAB: u16 = 7;
+pub const NET_ENEMY_SQUID: u16 = 8;
+pub const NET_POWER_UP_RAPID_FIRE: u16 = 9;
+pub const NET_POWER_UP_SPREAD_SHOT: u16 = 10;
+pub const NET_POWER_UP_PIERCING: u16 = 11;
+pub const NET_POWER_UP_SHIELD: u16 = 12;
+pub const NET_POWER_UP_EXTRA_LIFE: u16 = 13;
+/// Boss parts are sent as `NET_BOSS_PART + part index`
+pub const NET_BOSS_PART: u16 = 14;
+/// Boss health, sent as `(NET_BOSS_HEALTH, hp, max_hp)` instead of a position
+pub const NET_BOSS_HEALTH: u16 = 20;
+/// Animation frame of the formation, sent as `(NET_FORMATION_FRAME, frame, 0)`
+pub const NET_FORMATION_FRAME: u16 = 21;
+/// Effects are sent as their base code + the frame shown
+pub const NET_EFFECT_ENEMY_EXPLOSION: u16 = 22;
+pub const NET_EFFECT_PLAYER_EXPLOSION: u16 = 26;
+pub const NET_EFFECT_SHOT_BURST: u16 = 30;
+
 #[derive(Serialize, Deserialize, Debug)]
 pub enum NetPacket {
-    PlayerInput { x: f32, shoot: bool },
-    GameStateUpdate { entities: Vec<(u16, u16, u16)> },
+    PlayerInput {
+        x: f32,
+        shoot: bool,
+    },
+    GameStateUpdate {
+        entities: Vec<(u16, u16, u16)>,
+        /// Bunker cells as `(x, y, hp)`
+        bunkers: Vec<(u16, u16, u8)>,
+    },
+    /// Either player paused or resumed, both games follow
+    Pause {
+        paused: bool,
+    },
 }
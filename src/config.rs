@@ -0,0 +1,101 @@
+use invaderse::{HighScores, Keymap, SaveGame, Settings};
+use std::error::Error;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory holding the config files, e.g. `~/.config/invaderse` on Linux
+fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("invaderse"))
+}
+
+fn keymap_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("keys.toml"))
+}
+
+fn settings_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("settings.toml"))
+}
+
+fn high_scores_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("scores.toml"))
+}
+
+fn save_path() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("save.toml"))
+}
+
+/// Loads the key bindings, a missing file means the defaults. Invalid files fall back
+/// to the defaults, with the problems returned to be shown on screen.
+pub fn load_keymap() -> (Keymap, Vec<String>) {
+    let Some(path) = keymap_path().filter(|path| path.exists()) else {
+        return (Keymap::default(), Vec::new());
+    };
+    match Keymap::load(&path) {
+        Ok(keymap) => (keymap, Vec::new()),
+        Err(errors) => (Keymap::default(), errors),
+    }
+}
+
+pub fn save_keymap(keymap: &Keymap) -> Result<(), Box<dyn Error>> {
+    write_config(keymap_path(), &keymap.to_toml())
+}
+
+/// Loads the settings like the key bindings, falling back to the defaults
+pub fn load_settings() -> (Settings, Vec<String>) {
+    let Some(path) = settings_path().filter(|path| path.exists()) else {
+        return (Settings::default(), Vec::new());
+    };
+    match Settings::load(&path) {
+        Ok(settings) => (settings, Vec::new()),
+        Err(errors) => (Settings::default(), errors),
+    }
+}
+
+pub fn save_settings(settings: &Settings) -> Result<(), Box<dyn Error>> {
+    write_config(settings_path(), &settings.to_toml())
+}
+
+/// Loads the high scores, an unreadable file starts the records over
+pub fn load_high_scores() -> HighScores {
+    high_scores_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| HighScores::parse(&text).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_high_scores(high_scores: &HighScores) -> Result<(), Box<dyn Error>> {
+    write_config(high_scores_path(), &high_scores.to_toml())
+}
+
+/// The game saved from the pause menu, if there is one
+pub fn load_save() -> Result<Option<SaveGame>, Box<dyn Error>> {
+    let Some(path) = save_path().filter(|path| path.exists()) else {
+        return Ok(None);
+    };
+    let save = SaveGame::parse(&fs::read_to_string(path)?)?;
+    Ok(Some(save))
+}
+
+pub fn save_game(save: &SaveGame) -> Result<(), Box<dyn Error>> {
+    write_config(save_path(), &save.to_toml())
+}
+
+/// Writes a crash report next to the config files, returning where it went
+pub fn write_crash_report(report: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let path = config_dir()
+        .ok_or("no config directory found")?
+        .join(format!("crash-{seconds}.txt"));
+    write_config(Some(path.clone()), report)?;
+    Ok(path)
+}
+
+fn write_config(path: Option<PathBuf>, contents: &str) -> Result<(), Box<dyn Error>> {
+    let path = path.ok_or("no config directory found")?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, contents)?;
+    Ok(())
+}
@@ -1,12 +1,17 @@
-use crate::{Direction, GameState, MenuItem, NetPacket, Player, Render, Screen, Velocity};
-use std::time::Duration;
+use crate::NetSession;
+use crate::config::{load_save, save_game, save_keymap, save_settings};
+use invaderse::{
+    Action, ControlsMenu, CrosstermBackend, DEFAULT_RELEASE_TIMEOUT, Direction,
+    FIRST_REPEAT_TIMEOUT, GameState, Key, MenuEntry, MenuItem, NetPacket, PauseItem, PauseMenu,
+    Player, Render, RepeatTracker, SaveGame, Screen, SettingsItem, SettingsMenu, Velocity,
+};
+use std::time::{Duration, Instant};
 
-use rand::distr::weighted;
 use std::net::SocketAddr;
 use tokio::sync::mpsc;
 
 use crossterm::{
-    event::{Event, KeyCode},
+    event::{Event, KeyCode, MouseButton, MouseEventKind},
     terminal,
 };
 
@@ -22,16 +27,266 @@ pub enum GameEvent {
     PlayerShootEnd,
     Pause,
     Restart,
+    /// The process was asked to stop or lost its terminal
+    Terminate,
+    /// Co-player keys in local co-op
+    CoPlayerLeft,
+    CoPlayerLeftEnd,
+    CoPlayerRight,
+    CoPlayerRightEnd,
+    CoPlayerShoot,
+    CoPlayerShootEnd,
+    /// Raw key presses and releases, turned into the events above by `map_key`
+    KeyPressed(Key),
+    KeyReleased(Key),
+    /// Mouse position as terminal column and row, only reported with `--mouse`
+    MouseMoved(u16, u16),
+    MousePressed(u16, u16),
+    MouseReleased,
     PeerConnected(SocketAddr, mpsc::UnboundedSender<NetPacket>),
     PacketReceived(NetPacket),
 }
 
-pub fn handle_event(event: GameEvent, renderer: &mut Render, game_state: &mut GameState) -> bool {
+/// Keys that are down, as reported by the terminal or inferred from auto-repeat
+pub struct KeyInput {
+    /// Lets releasing one of several keys bound to the same action keep it going
+    held: Vec<Key>,
+    /// Set on terminals without key release events
+    repeat: Option<RepeatTracker>,
+}
+
+impl KeyInput {
+    /// Infers key releases from auto-repeat unless the terminal reports them. A given
+    /// release timeout also caps the wait for the first repeat, making taps shorter at
+    /// the cost of holds stuttering when it is below the terminal's repeat delay.
+    pub fn new(kb_enhanced: bool, release_timeout: Option<Duration>) -> Self {
+        let tracker = match release_timeout {
+            Some(timeout) => RepeatTracker::new(timeout)
+                .with_first_repeat_timeout(timeout.min(FIRST_REPEAT_TIMEOUT)),
+            None => RepeatTracker::new(DEFAULT_RELEASE_TIMEOUT),
+        };
+        Self {
+            held: Vec::new(),
+            repeat: (!kb_enhanced).then_some(tracker),
+        }
+    }
+
+    /// Release events for keys whose auto-repeat stopped
+    pub fn expired(&mut self) -> Vec<GameEvent> {
+        match self.repeat {
+            Some(ref mut repeat) => repeat
+                .expire(Instant::now())
+                .into_iter()
+                .map(GameEvent::KeyReleased)
+                .collect(),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Turns raw key events into game events through the keymap. While the Controls
+/// screen waits for a key, the key pressed is bound instead, and while a text option
+/// is edited on the Settings screen keys are typed into it.
+pub fn map_key(
+    event: GameEvent,
+    game_state: &mut GameState,
+    input: &mut KeyInput,
+) -> Option<GameEvent> {
+    match event {
+        GameEvent::KeyPressed(key) => {
+            if let Screen::Settings = game_state.main_menu.screen
+                && game_state.settings_menu.editing.is_some()
+            {
+                edit_setting(game_state, key);
+                return None;
+            }
+
+            // Auto-repeats only keep the key held
+            if let Some(ref mut repeat) = input.repeat
+                && !repeat.press(key, Instant::now())
+            {
+                return None;
+            }
+
+            if let Screen::Controls = game_state.main_menu.screen
+                && game_state.controls_menu.capturing
+            {
+                let menu = &mut game_state.controls_menu;
+                menu.capturing = false;
+                let result = game_state.keymap.toggle(menu.action(), key);
+                apply_controls_change(game_state, result);
+                return None;
+            }
+
+            if !input.held.contains(&key) {
+                input.held.push(key);
+            }
+            Some(match key_action(game_state, key)? {
+                Action::MoveLeft => GameEvent::MovePlayerLeft,
+                Action::MoveRight => GameEvent::MovePlayerRight,
+                Action::Shoot => GameEvent::PlayerShoot,
+                Action::Pause => GameEvent::Pause,
+                Action::Restart => GameEvent::Restart,
+                Action::Quit => GameEvent::Quit,
+                Action::CoopLeft => GameEvent::CoPlayerLeft,
+                Action::CoopRight => GameEvent::CoPlayerRight,
+                Action::CoopShoot => GameEvent::CoPlayerShoot,
+            })
+        }
+        GameEvent::KeyReleased(key) => {
+            input.held.retain(|&k| k != key);
+            let action = key_action(game_state, key)?;
+            if input
+                .held
+                .iter()
+                .any(|&k| key_action(game_state, k) == Some(action))
+            {
+                return None;
+            }
+            match action {
+                Action::MoveLeft => Some(GameEvent::MovePlayerLeftEnd),
+                Action::MoveRight => Some(GameEvent::MovePlayerRightEnd),
+                Action::Shoot => Some(GameEvent::PlayerShootEnd),
+                Action::CoopLeft => Some(GameEvent::CoPlayerLeftEnd),
+                Action::CoopRight => Some(GameEvent::CoPlayerRightEnd),
+                Action::CoopShoot => Some(GameEvent::CoPlayerShootEnd),
+                _ => None,
+            }
+        }
+        other => Some(other),
+    }
+}
+
+/// Types into the option edited on the Settings screen, enter saves and tab cancels
+fn edit_setting(game_state: &mut GameState, key: Key) {
+    let menu = &mut game_state.settings_menu;
+    let Some(ref mut text) = menu.editing else {
+        return;
+    };
+    match key {
+        Key::Char(ch) => text.push(ch),
+        Key::Backspace => {
+            text.pop();
+        }
+        Key::Enter => {
+            let text = menu.editing.take().unwrap_or_default();
+            let result = game_state.settings.set_text(menu.item(), &text);
+            apply_settings_change(game_state, result);
+        }
+        Key::Tab => menu.editing = None,
+        _ => (),
+    }
+}
+
+/// Action the key triggers, during local co-op the co-player's keys come first
+fn key_action(game_state: &GameState, key: Key) -> Option<Action> {
+    let keymap = &game_state.keymap;
+    let coop = game_state.local_coop && matches!(game_state.main_menu.screen, Screen::Game);
+    coop.then(|| keymap.coop_action(key))
+        .flatten()
+        .or_else(|| keymap.action(key))
+}
+
+/// Writes the keymap after a change on the Controls screen, rejected changes and
+/// failures to save are shown there
+fn apply_controls_change(game_state: &mut GameState, result: Result<(), String>) {
+    let saved = result.and_then(|()| {
+        save_keymap(&game_state.keymap).map_err(|e| format!("Could not save key bindings: {e}"))
+    });
+    game_state.controls_menu.message = saved.err();
+}
+
+/// Writes the settings after a change on the Settings screen. The game waiting on the
+/// main menu was set up with the old settings, so the next one starts over. A game
+/// paused to change them keeps going with the old ones.
+fn apply_settings_change(game_state: &mut GameState, result: Result<(), String>) {
+    if result.is_ok() && !game_state.settings_menu.in_game {
+        game_state.restart_notifier = true;
+    }
+    let saved = result.and_then(|()| {
+        save_settings(&game_state.settings).map_err(|e| format!("Could not save settings: {e}"))
+    });
+    game_state.settings_menu.message = saved.err();
+}
+
+/// Acts on the selected pause menu entry, entries throwing the game away ask first
+fn choose_pause_item(game_state: &mut GameState) {
+    let menu = &mut game_state.pause_menu;
+    let item = menu.item();
+    if item.needs_confirmation() && !menu.confirming {
+        menu.confirming = true;
+        return;
+    }
+    menu.confirming = false;
+
+    match item {
+        PauseItem::Resume => game_state.pause_notifier = true,
+        PauseItem::Restart => game_state.restart_notifier = true,
+        PauseItem::Settings => {
+            game_state.main_menu.screen = Screen::Settings;
+            game_state.settings_menu = SettingsMenu {
+                in_game: true,
+                ..SettingsMenu::default()
+            };
+            game_state.request_clear_render = true;
+        }
+        PauseItem::Save => {
+            let message = if game_state.networking.connected {
+                "Saving only works offline".to_string()
+            } else {
+                match save_game(&SaveGame::from_game(game_state)) {
+                    Ok(()) => "Saved, Continue starts this wave over".to_string(),
+                    Err(e) => format!("Could not save the game: {e}"),
+                }
+            };
+            game_state.pause_menu.message = Some(message);
+        }
+        PauseItem::QuitToMenu => game_state.exit_to_menu(),
+    }
+}
+
+/// Restart and quit keys pressed mid-game pause it and ask for confirmation through
+/// the pause menu, pressing the key again confirms
+pub fn confirm_in_pause_menu(game_state: &mut GameState, item: PauseItem) {
+    if !game_state.paused {
+        game_state.set_paused(true);
+    }
+    let menu = &mut game_state.pause_menu;
+    if menu.item() == item && menu.confirming {
+        choose_pause_item(game_state);
+        return;
+    }
+    menu.selected = PauseItem::ALL.iter().position(|&i| i == item).unwrap_or(0);
+    menu.confirming = true;
+    menu.message = None;
+}
+
+/// Starts the game saved from the pause menu, the restart sets it up
+fn continue_saved_game(game_state: &mut GameState) {
+    match load_save() {
+        Ok(Some(save)) => {
+            game_state.resume = Some(save);
+            game_state.restart_notifier = true;
+            game_state.main_menu.screen = Screen::Game;
+            game_state.request_clear_render = true;
+        }
+        Ok(None) => game_state.main_menu.message = Some("No saved game to continue".to_string()),
+        Err(e) => {
+            game_state.main_menu.message = Some(format!("Could not load the saved game: {e}"));
+        }
+    }
+}
+
+pub fn handle_event(
+    event: GameEvent,
+    renderer: &mut Render<CrosstermBackend>,
+    game_state: &mut GameState,
+    net: &mut NetSession,
+) -> bool {
     match event {
         GameEvent::ResizeGame => {
-            renderer.wsize_updated = true;
-            if let Ok(size) = terminal::window_size() {
-                renderer.wsize = size;
+            if let Ok((columns, rows)) = terminal::size() {
+                renderer.resize(columns, rows);
             }
             let _ = renderer.render(game_state); // render immediately to reflect new bounds
             false
@@ -40,6 +295,7 @@ pub fn handle_event(event: GameEvent, renderer: &mut Render, game_state: &mut Ga
             // Handle while In Menu
             match game_state.main_menu.screen {
                 Screen::Main => {
+                    game_state.main_menu.message = None;
                     match game_state.main_menu.active_menu_item {
                         MenuItem::HostGame => {
                             game_state.main_menu.screen = Screen::Hosting;
@@ -55,21 +311,58 @@ pub fn handle_event(event: GameEvent, renderer: &mut Render, game_state: &mut Ga
                             game_state.main_menu.screen = Screen::Game;
                             game_state.request_clear_render = true;
                         }
+                        MenuItem::LocalCoop => {
+                            game_state.main_menu.screen = Screen::Game;
+                            game_state.request_clear_render = true;
+                            game_state.local_coop = true;
+                        }
+                        MenuItem::Continue => continue_saved_game(game_state),
+                        MenuItem::Controls => {
+                            game_state.main_menu.screen = Screen::Controls;
+                            game_state.controls_menu = ControlsMenu::default();
+                            game_state.request_clear_render = true;
+                        }
+                        MenuItem::Settings => {
+                            game_state.main_menu.screen = Screen::Settings;
+                            game_state.settings_menu = SettingsMenu::default();
+                            game_state.request_clear_render = true;
+                        }
+                    }
+                    false
+                }
+                Screen::Settings => {
+                    let item = game_state.settings_menu.item();
+                    game_state.settings_menu.message = None;
+                    if let SettingsItem::KeyBindings = item {
+                        game_state.main_menu.screen = Screen::Controls;
+                        game_state.controls_menu = ControlsMenu::default();
+                        game_state.request_clear_render = true;
+                    } else if item.is_text() {
+                        game_state.settings_menu.editing = Some(game_state.settings.value(item));
+                    } else {
+                        game_state.settings.cycle(item);
+                        apply_settings_change(game_state, Ok(()));
                     }
                     false
                 }
+                Screen::Controls => {
+                    game_state.controls_menu.capturing = true;
+                    game_state.controls_menu.message = None;
+                    false
+                }
                 Screen::Hosting => {
-                    if game_state.networking.tx_writer.is_some() {
+                    if game_state.networking.connected {
                         game_state.main_menu.screen = Screen::Game;
                         game_state.request_clear_render = true;
                     }
                     false
                 }
+                Screen::Game if game_state.paused => {
+                    choose_pause_item(game_state);
+                    false
+                }
                 Screen::Game => {
-                    game_state.player_input_handler.player_shoot = true;
-                    if !game_state.networking.host {
-                        game_state.coplayer_handler.player_shoot = true;
-                    }
+                    game_state.press_shoot();
                     false
                 }
                 _ => false,
@@ -81,22 +374,33 @@ pub fn handle_event(event: GameEvent, renderer: &mut Render, game_state: &mut Ga
         }
         GameEvent::MovePlayerLeft => {
             // Handle when in menu
-            if let Screen::Main = game_state.main_menu.screen {
-                match game_state.main_menu.active_menu_item {
-                    MenuItem::HostGame => {
-                        game_state.main_menu.active_menu_item = MenuItem::PlaySolo;
-                    }
-                    MenuItem::JoinGame => {
-                        game_state.main_menu.active_menu_item = MenuItem::HostGame;
-                    }
-                    MenuItem::PlaySolo => {
-                        game_state.main_menu.active_menu_item = MenuItem::JoinGame;
-                    }
+            match game_state.main_menu.screen {
+                Screen::Main => {
+                    game_state.main_menu.active_menu_item =
+                        game_state.main_menu.active_menu_item.previous();
+                    game_state.main_menu.message = None;
+                    return false;
                 }
-                return false;
+                Screen::Game if game_state.paused => {
+                    game_state.pause_menu.select(-1);
+                    return false;
+                }
+                Screen::Controls => {
+                    let menu = &mut game_state.controls_menu;
+                    menu.selected = (menu.selected + Action::ALL.len() - 1) % Action::ALL.len();
+                    return false;
+                }
+                Screen::Settings => {
+                    let menu = &mut game_state.settings_menu;
+                    let count = SettingsItem::ALL.len();
+                    menu.selected = (menu.selected + count - 1) % count;
+                    return false;
+                }
+                _ => (),
             }
 
             game_state.player_input_handler.move_player_left = true;
+            game_state.player_input_handler.mouse_target = None;
 
             // TODO: Replace with direct access with Player entity stored in game_state
             for (_, vel) in game_state
@@ -125,22 +429,32 @@ pub fn handle_event(event: GameEvent, renderer: &mut Render, game_state: &mut Ga
         }
         GameEvent::MovePlayerRight => {
             // Handle when in menu
-            if let Screen::Main = game_state.main_menu.screen {
-                match game_state.main_menu.active_menu_item {
-                    MenuItem::HostGame => {
-                        game_state.main_menu.active_menu_item = MenuItem::JoinGame;
-                    }
-                    MenuItem::JoinGame => {
-                        game_state.main_menu.active_menu_item = MenuItem::PlaySolo;
-                    }
-                    MenuItem::PlaySolo => {
-                        game_state.main_menu.active_menu_item = MenuItem::HostGame;
-                    }
+            match game_state.main_menu.screen {
+                Screen::Main => {
+                    game_state.main_menu.active_menu_item =
+                        game_state.main_menu.active_menu_item.next();
+                    game_state.main_menu.message = None;
+                    return false;
                 }
-                return false;
+                Screen::Game if game_state.paused => {
+                    game_state.pause_menu.select(1);
+                    return false;
+                }
+                Screen::Controls => {
+                    let menu = &mut game_state.controls_menu;
+                    menu.selected = (menu.selected + 1) % Action::ALL.len();
+                    return false;
+                }
+                Screen::Settings => {
+                    let menu = &mut game_state.settings_menu;
+                    menu.selected = (menu.selected + 1) % SettingsItem::ALL.len();
+                    return false;
+                }
+                _ => (),
             }
 
             game_state.player_input_handler.move_player_right = true;
+            game_state.player_input_handler.mouse_target = None;
 
             for (_, vel) in game_state
                 .world
@@ -167,20 +481,108 @@ pub fn handle_event(event: GameEvent, renderer: &mut Render, game_state: &mut Ga
             }
             false
         }
+        GameEvent::CoPlayerLeft => {
+            game_state.coplayer_handler.move_left = true;
+            false
+        }
+        GameEvent::CoPlayerLeftEnd => {
+            game_state.coplayer_handler.move_left = false;
+            false
+        }
+        GameEvent::CoPlayerRight => {
+            game_state.coplayer_handler.move_right = true;
+            false
+        }
+        GameEvent::CoPlayerRightEnd => {
+            game_state.coplayer_handler.move_right = false;
+            false
+        }
+        GameEvent::CoPlayerShoot => {
+            game_state.coplayer_handler.shoot_held = true;
+            false
+        }
+        GameEvent::CoPlayerShootEnd => {
+            game_state.coplayer_handler.shoot_held = false;
+            false
+        }
         GameEvent::Pause => {
-            if !game_state.game_over {
+            if let Screen::Controls = game_state.main_menu.screen {
+                let result = game_state.keymap.reset(game_state.controls_menu.action());
+                apply_controls_change(game_state, result);
+                return false;
+            }
+            if let Screen::Game = game_state.main_menu.screen
+                && !game_state.game_over
+            {
                 game_state.pause_notifier = true;
             }
             false
         }
         GameEvent::Restart => {
-            game_state.restart_notifier = true;
+            match game_state.main_menu.screen {
+                Screen::Game if !game_state.game_over => {
+                    confirm_in_pause_menu(game_state, PauseItem::Restart);
+                }
+                Screen::Controls | Screen::Settings => (),
+                _ => game_state.restart_notifier = true,
+            }
             false
         }
+        GameEvent::MouseMoved(column, row) => {
+            match game_state.main_menu.screen {
+                Screen::Main => {
+                    if let Some(MenuEntry::Item(item)) =
+                        renderer.menu_entry_at(game_state, column, row)
+                    {
+                        game_state.main_menu.active_menu_item = item;
+                    }
+                }
+                Screen::Game => {
+                    if let Some(x) = renderer.field_column(column, row) {
+                        // Center the five cells wide cannon under the pointer
+                        game_state.player_input_handler.mouse_target = Some(x.saturating_sub(2));
+                    }
+                }
+                _ => (),
+            }
+            false
+        }
+        GameEvent::MousePressed(column, row) => {
+            if let Screen::Game = game_state.main_menu.screen {
+                if game_state.paused || renderer.field_column(column, row).is_none() {
+                    return false;
+                }
+                handle_event(
+                    GameEvent::MouseMoved(column, row),
+                    renderer,
+                    game_state,
+                    net,
+                );
+                return handle_event(GameEvent::PlayerShoot, renderer, game_state, net);
+            }
+
+            match renderer.menu_entry_at(game_state, column, row) {
+                Some(MenuEntry::Item(item)) => {
+                    game_state.main_menu.active_menu_item = item;
+                    handle_event(GameEvent::PlayerShoot, renderer, game_state, net)
+                }
+                Some(MenuEntry::Start) => {
+                    handle_event(GameEvent::PlayerShoot, renderer, game_state, net)
+                }
+                Some(MenuEntry::Back) => {
+                    game_state.exit_to_menu();
+                    false
+                }
+                None => false,
+            }
+        }
+        GameEvent::MouseReleased => {
+            handle_event(GameEvent::PlayerShootEnd, renderer, game_state, net)
+        }
         GameEvent::Tick => true,
         GameEvent::PeerConnected(addr, tx_writer) => {
-            game_state.networking.peer = Some(addr);
-            game_state.networking.tx_writer = Some(tx_writer);
+            game_state.networking.connect(addr);
+            net.tx_writer = Some(tx_writer);
             false
         }
         GameEvent::PacketReceived(packet) => match packet {
@@ -193,14 +595,21 @@ pub fn handle_event(event: GameEvent, renderer: &mut Render, game_state: &mut Ga
                 game_state.coplayer_handler.player_shoot = shoot;
                 false
             }
-            NetPacket::GameStateUpdate { entities } => {
-                game_state.coplayer_handler.old_host_entities =
-                    game_state.coplayer_handler.host_entities.clone();
+            NetPacket::GameStateUpdate { entities, bunkers } => {
                 game_state.coplayer_handler.host_entities = Some(entities);
+                game_state.coplayer_handler.host_bunkers = bunkers;
+                false
+            }
+            NetPacket::Pause { paused } => {
+                game_state.paused = paused;
+                game_state.pause_menu = PauseMenu::default();
                 false
             }
         },
-        GameEvent::Quit => false,
+        GameEvent::Quit
+        | GameEvent::Terminate
+        | GameEvent::KeyPressed(_)
+        | GameEvent::KeyReleased(_) => false,
     }
 }
 
@@ -217,6 +626,28 @@ pub fn spawn_coordination_threads(tx_main: &mpsc::UnboundedSender<GameEvent>) {
         }
     });
 
+    // Quit through the normal exit path, which restores the terminal, when asked to
+    // stop or when the terminal goes away
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+
+        let tx_signal = tx_main.clone();
+        tokio::spawn(async move {
+            let (Ok(mut terminate), Ok(mut hangup)) = (
+                signal(SignalKind::terminate()),
+                signal(SignalKind::hangup()),
+            ) else {
+                return;
+            };
+            tokio::select! {
+                _ = terminate.recv() => (),
+                _ = hangup.recv() => (),
+            }
+            let _ = tx_signal.send(GameEvent::Terminate);
+        });
+    }
+
     // handle events
     let tx = tx_main.clone();
     tokio::task::spawn_blocking(move || {
@@ -224,51 +655,34 @@ pub fn spawn_coordination_threads(tx_main: &mpsc::UnboundedSender<GameEvent>) {
             match crossterm::event::read() {
                 Ok(event) => match event {
                     Event::Key(key_event) => {
-                        if key_event.code == KeyCode::Char('q') && key_event.is_press() {
-                            match tx.send(GameEvent::Quit) {
-                                Ok(_) => continue,
-                                Err(_) => break,
-                            }
-                        } else if key_event.code == KeyCode::Char('a') && key_event.is_press() {
-                            match tx.send(GameEvent::MovePlayerLeft) {
-                                Ok(_) => continue,
-                                Err(_) => break,
-                            }
-                        } else if key_event.code == KeyCode::Char('d') && key_event.is_press() {
-                            match tx.send(GameEvent::MovePlayerRight) {
-                                Ok(_) => continue,
-                                Err(_) => break,
-                            }
-                        } else if key_event.code == KeyCode::Char('a') && key_event.is_release() {
-                            match tx.send(GameEvent::MovePlayerLeftEnd) {
-                                Ok(_) => continue,
-                                Err(_) => break,
-                            }
-                        } else if key_event.code == KeyCode::Char('d') && key_event.is_release() {
-                            match tx.send(GameEvent::MovePlayerRightEnd) {
-                                Ok(_) => continue,
-                                Err(_) => break,
-                            }
-                        } else if key_event.code == KeyCode::Char('w') && key_event.is_press() {
-                            match tx.send(GameEvent::PlayerShoot) {
-                                Ok(_) => continue,
-                                Err(_) => break,
-                            }
-                        } else if key_event.code == KeyCode::Char('w') && key_event.is_release() {
-                            match tx.send(GameEvent::PlayerShootEnd) {
-                                Ok(_) => continue,
-                                Err(_) => break,
-                            }
-                        } else if key_event.code == KeyCode::Char('p') && key_event.is_press() {
-                            match tx.send(GameEvent::Pause) {
-                                Ok(_) => continue,
-                                Err(_) => break,
+                        let Some(key) = key_from_code(key_event.code) else {
+                            continue;
+                        };
+                        let event = if key_event.is_press() {
+                            GameEvent::KeyPressed(key)
+                        } else if key_event.is_release() {
+                            GameEvent::KeyReleased(key)
+                        } else {
+                            continue;
+                        };
+                        if tx.send(event).is_err() {
+                            break;
+                        }
+                    }
+                    Event::Mouse(mouse) => {
+                        let (column, row) = (mouse.column, mouse.row);
+                        let event = match mouse.kind {
+                            MouseEventKind::Moved | MouseEventKind::Drag(MouseButton::Left) => {
+                                GameEvent::MouseMoved(column, row)
                             }
-                        } else if key_event.code == KeyCode::Char('r') && key_event.is_press() {
-                            match tx.send(GameEvent::Restart) {
-                                Ok(_) => continue,
-                                Err(_) => break,
+                            MouseEventKind::Down(MouseButton::Left) => {
+                                GameEvent::MousePressed(column, row)
                             }
+                            MouseEventKind::Up(MouseButton::Left) => GameEvent::MouseReleased,
+                            _ => continue,
+                        };
+                        if tx.send(event).is_err() {
+                            break;
                         }
                     }
                     Event::Resize(_, _) => match tx.send(GameEvent::ResizeGame) {
@@ -284,3 +698,17 @@ pub fn spawn_coordination_threads(tx_main: &mpsc::UnboundedSender<GameEvent>) {
         }
     });
 }
+
+fn key_from_code(code: KeyCode) -> Option<Key> {
+    match code {
+        KeyCode::Char(ch) => Some(Key::Char(ch.to_ascii_lowercase())),
+        KeyCode::Left => Some(Key::Left),
+        KeyCode::Right => Some(Key::Right),
+        KeyCode::Up => Some(Key::Up),
+        KeyCode::Down => Some(Key::Down),
+        KeyCode::Enter => Some(Key::Enter),
+        KeyCode::Tab => Some(Key::Tab),
+        KeyCode::Backspace => Some(Key::Backspace),
+        _ => None,
+    }
+}
@@ -0,0 +1,125 @@
+use crate::Key;
+use std::time::{Duration, Instant};
+
+/// Release timeout used unless `--release-timeout` is given
+pub const DEFAULT_RELEASE_TIMEOUT: Duration = Duration::from_millis(150);
+
+/// Longest wait for the first auto-repeat of a press, above the 660ms repeat delay
+/// X11 defaults to
+pub const FIRST_REPEAT_TIMEOUT: Duration = Duration::from_millis(700);
+
+/// A second press sooner than this is a tap, auto-repeat never kicks in that early
+const MIN_REPEAT_DELAY: Duration = Duration::from_millis(200);
+
+/// Leeway around the learned repeat delay for a late or early first repeat
+const REPEAT_DELAY_SLACK: Duration = Duration::from_millis(50);
+
+/// Infers held keys on terminals that only report key presses.
+///
+/// Auto-repeat keeps pressing a held key, so a key counts as released once its presses
+/// stop for longer than the release timeout. The pause before auto-repeat kicks in is
+/// much longer than the gap between repeats, so a fresh press is held until its first
+/// repeat is due. That pause is learned from the first key held down, until then it is
+/// bounded by the first repeat timeout. A second press that comes sooner than a repeat
+/// could is a tap of its own.
+pub struct RepeatTracker {
+    release_timeout: Duration,
+    first_repeat_timeout: Duration,
+    /// Pause between a press and its first auto-repeat, once a key was held down
+    repeat_delay: Option<Duration>,
+    held: Vec<HeldKey>,
+}
+
+struct HeldKey {
+    key: Key,
+    last_press: Instant,
+    hold: Hold,
+}
+
+#[derive(Clone, Copy)]
+enum Hold {
+    /// Waiting for the first auto-repeat
+    Pressed,
+    /// Pressed again after the pause, confirmed as auto-repeat by a quick next press
+    FirstRepeat(Duration),
+    Repeating,
+}
+
+impl RepeatTracker {
+    pub fn new(release_timeout: Duration) -> Self {
+        Self {
+            release_timeout,
+            first_repeat_timeout: FIRST_REPEAT_TIMEOUT,
+            repeat_delay: None,
+            held: Vec::new(),
+        }
+    }
+
+    /// Bounds how long a press is held while waiting for its first auto-repeat
+    pub fn with_first_repeat_timeout(mut self, timeout: Duration) -> Self {
+        self.first_repeat_timeout = timeout;
+        self
+    }
+
+    /// How long a press is held before its first auto-repeat is overdue
+    fn first_repeat_wait(&self) -> Duration {
+        match self.repeat_delay {
+            Some(delay) => (delay + REPEAT_DELAY_SLACK).min(self.first_repeat_timeout),
+            None => self.first_repeat_timeout,
+        }
+    }
+
+    /// Records a press, returns false for auto-repeats of a key already held
+    pub fn press(&mut self, key: Key, now: Instant) -> bool {
+        let first_repeat_wait = self.first_repeat_wait();
+        let min_repeat_delay = match self.repeat_delay {
+            Some(delay) => delay.saturating_sub(REPEAT_DELAY_SLACK),
+            None => MIN_REPEAT_DELAY,
+        };
+
+        let Some(held) = self.held.iter_mut().find(|held| held.key == key) else {
+            self.held.push(HeldKey {
+                key,
+                last_press: now,
+                hold: Hold::Pressed,
+            });
+            return true;
+        };
+
+        let gap = now.duration_since(held.last_press);
+        held.last_press = now;
+        held.hold = match held.hold {
+            Hold::Pressed if (min_repeat_delay..=first_repeat_wait).contains(&gap) => {
+                Hold::FirstRepeat(gap)
+            }
+            Hold::FirstRepeat(delay) if gap <= self.release_timeout => {
+                self.repeat_delay = Some(delay);
+                Hold::Repeating
+            }
+            Hold::Repeating if gap <= self.release_timeout => Hold::Repeating,
+            _ => {
+                held.hold = Hold::Pressed;
+                return true;
+            }
+        };
+        false
+    }
+
+    /// Removes and returns the keys whose presses stopped
+    pub fn expire(&mut self, now: Instant) -> Vec<Key> {
+        let first_repeat_wait = self.first_repeat_wait();
+        let mut released = Vec::new();
+        self.held.retain(|held| {
+            let timeout = match held.hold {
+                Hold::Pressed => first_repeat_wait,
+                Hold::FirstRepeat(_) | Hold::Repeating => self.release_timeout,
+            };
+            let still_held = now.duration_since(held.last_press) <= timeout;
+            if !still_held {
+                released.push(held.key);
+            }
+            still_held
+        });
+        released
+    }
+}
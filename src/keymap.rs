@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+/// Keys that can be bound to an action, independent of the terminal library
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Left,
+    Right,
+    Up,
+    Down,
+    Enter,
+    Tab,
+    Backspace,
+}
+
+impl Key {
+    /// Parses the name used in the keymap file, e.g. `a`, `left` or `space`
+    pub fn from_name(name: &str) -> Option<Key> {
+        match name {
+            "left" => Some(Key::Left),
+            "right" => Some(Key::Right),
+            "up" => Some(Key::Up),
+            "down" => Some(Key::Down),
+            "enter" => Some(Key::Enter),
+            "tab" => Some(Key::Tab),
+            "backspace" => Some(Key::Backspace),
+            "space" => Some(Key::Char(' ')),
+            _ => {
+                let mut chars = name.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(ch), None) if !ch.is_whitespace() => {
+                        Some(Key::Char(ch.to_ascii_lowercase()))
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(' ') => write!(f, "space"),
+            Key::Char(ch) => write!(f, "{ch}"),
+            Key::Left => write!(f, "left"),
+            Key::Right => write!(f, "right"),
+            Key::Up => write!(f, "up"),
+            Key::Down => write!(f, "down"),
+            Key::Enter => write!(f, "enter"),
+            Key::Tab => write!(f, "tab"),
+            Key::Backspace => write!(f, "backspace"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    Shoot,
+    Pause,
+    Restart,
+    Quit,
+    /// Second key set driving the co-player in local co-op
+    CoopLeft,
+    CoopRight,
+    CoopShoot,
+}
+
+impl Action {
+    pub const ALL: [Action; 9] = [
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::Shoot,
+        Action::Pause,
+        Action::Restart,
+        Action::Quit,
+        Action::CoopLeft,
+        Action::CoopRight,
+        Action::CoopShoot,
+    ];
+
+    /// Name of the action in the keymap file
+    pub const fn name(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::Shoot => "shoot",
+            Action::Pause => "pause",
+            Action::Restart => "restart",
+            Action::Quit => "quit",
+            Action::CoopLeft => "coop_left",
+            Action::CoopRight => "coop_right",
+            Action::CoopShoot => "coop_shoot",
+        }
+    }
+
+    /// Name shown on the Controls screen
+    pub const fn label(self) -> &'static str {
+        match self {
+            Action::MoveLeft => "Move left",
+            Action::MoveRight => "Move right",
+            Action::Shoot => "Shoot",
+            Action::Pause => "Pause",
+            Action::Restart => "Restart",
+            Action::Quit => "Quit",
+            Action::CoopLeft => "Co-op left",
+            Action::CoopRight => "Co-op right",
+            Action::CoopShoot => "Co-op shoot",
+        }
+    }
+
+    pub const fn default_keys(self) -> &'static [Key] {
+        match self {
+            Action::MoveLeft => &[Key::Char('a'), Key::Left],
+            Action::MoveRight => &[Key::Char('d'), Key::Right],
+            Action::Shoot => &[Key::Char('w'), Key::Char(' ')],
+            Action::Pause => &[Key::Char('p')],
+            Action::Restart => &[Key::Char('r')],
+            Action::Quit => &[Key::Char('q')],
+            Action::CoopLeft => &[Key::Left],
+            Action::CoopRight => &[Key::Right],
+            Action::CoopShoot => &[Key::Enter],
+        }
+    }
+
+    /// Co-player actions only apply during local co-op, so their keys may also be
+    /// bound to one of the other actions
+    pub const fn is_coop(self) -> bool {
+        matches!(
+            self,
+            Action::CoopLeft | Action::CoopRight | Action::CoopShoot
+        )
+    }
+
+    const fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Keys bound to every action. A key triggers at most one action, plus at most one
+/// co-player action.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    keys: [Vec<Key>; Action::ALL.len()],
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap {
+            keys: Action::ALL.map(|action| action.default_keys().to_vec()),
+        }
+    }
+}
+
+/// Actions left out of the file keep their default keys
+#[derive(Default, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+struct KeymapFile {
+    move_left: Option<Vec<String>>,
+    move_right: Option<Vec<String>>,
+    shoot: Option<Vec<String>>,
+    pause: Option<Vec<String>>,
+    restart: Option<Vec<String>>,
+    quit: Option<Vec<String>>,
+    coop_left: Option<Vec<String>>,
+    coop_right: Option<Vec<String>>,
+    coop_shoot: Option<Vec<String>>,
+}
+
+impl KeymapFile {
+    fn entry(&mut self, action: Action) -> &mut Option<Vec<String>> {
+        match action {
+            Action::MoveLeft => &mut self.move_left,
+            Action::MoveRight => &mut self.move_right,
+            Action::Shoot => &mut self.shoot,
+            Action::Pause => &mut self.pause,
+            Action::Restart => &mut self.restart,
+            Action::Quit => &mut self.quit,
+            Action::CoopLeft => &mut self.coop_left,
+            Action::CoopRight => &mut self.coop_right,
+            Action::CoopShoot => &mut self.coop_shoot,
+        }
+    }
+}
+
+impl Keymap {
+    /// Reads and validates a keymap file, returning every problem found
+    pub fn load(path: &Path) -> Result<Self, Vec<String>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| vec![format!("{}: {}", path.display(), e)])?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Vec<String>> {
+        let mut file: KeymapFile =
+            toml::from_str(text).map_err(|e| vec![e.message().to_string()])?;
+        let mut errors = Vec::new();
+        let mut keymap = Keymap::default();
+
+        for action in Action::ALL {
+            let Some(names) = file.entry(action).take() else {
+                continue;
+            };
+            if names.is_empty() {
+                errors.push(format!("{}: needs at least one key", action.name()));
+            }
+
+            let mut keys = Vec::new();
+            for name in names {
+                match Key::from_name(&name) {
+                    Some(key) if !keys.contains(&key) => keys.push(key),
+                    Some(_) => {}
+                    None => errors.push(format!("{}: unknown key '{name}'", action.name())),
+                }
+            }
+            keymap.keys[action.index()] = keys;
+        }
+
+        errors.extend(keymap.conflicts());
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(keymap)
+    }
+
+    /// Keymap file contents, listing every action
+    pub fn to_toml(&self) -> String {
+        let mut file = KeymapFile::default();
+        for action in Action::ALL {
+            *file.entry(action) = Some(self.keys(action).iter().map(Key::to_string).collect());
+        }
+        toml::to_string(&file).expect("keymap serializes to TOML")
+    }
+
+    pub fn keys(&self, action: Action) -> &[Key] {
+        &self.keys[action.index()]
+    }
+
+    /// Action the key is bound to, leaving out the co-player actions
+    pub fn action(&self, key: Key) -> Option<Action> {
+        self.bound(key, false)
+    }
+
+    /// Co-player action the key is bound to, used during local co-op
+    pub fn coop_action(&self, key: Key) -> Option<Action> {
+        self.bound(key, true)
+    }
+
+    fn bound(&self, key: Key, coop: bool) -> Option<Action> {
+        Action::ALL
+            .into_iter()
+            .find(|&action| action.is_coop() == coop && self.keys(action).contains(&key))
+    }
+
+    /// Key shown in hints, the first one bound to the action
+    pub fn hint(&self, action: Action) -> String {
+        self.keys(action)
+            .first()
+            .map_or_else(|| "?".to_string(), Key::to_string)
+    }
+
+    /// Binds the key to the action, or unbinds it if it already was.
+    /// Fails if the key belongs to another action or is the last key of this one.
+    pub fn toggle(&mut self, action: Action, key: Key) -> Result<(), String> {
+        match self.bound(key, action.is_coop()) {
+            Some(bound) if bound == action => {
+                if self.keys(action).len() == 1 {
+                    return Err(format!("{key} is the only key for {}", action.label()));
+                }
+                self.keys[action.index()].retain(|&k| k != key);
+            }
+            Some(bound) => {
+                return Err(format!("{key} is already bound to {}", bound.label()));
+            }
+            None => self.keys[action.index()].push(key),
+        }
+        Ok(())
+    }
+
+    pub fn reset(&mut self, action: Action) -> Result<(), String> {
+        for &key in action.default_keys() {
+            if let Some(bound) = self
+                .bound(key, action.is_coop())
+                .filter(|&bound| bound != action)
+            {
+                return Err(format!("{key} is already bound to {}", bound.label()));
+            }
+        }
+        self.keys[action.index()] = action.default_keys().to_vec();
+        Ok(())
+    }
+
+    /// Keys bound to more than one action of the same set
+    fn conflicts(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        for (index, &action) in Action::ALL.iter().enumerate() {
+            for &other in &Action::ALL[index + 1..] {
+                if action.is_coop() != other.is_coop() {
+                    continue;
+                }
+                for key in self.keys(action) {
+                    if self.keys(other).contains(key) {
+                        errors.push(format!(
+                            "{key} is bound to both {} and {}",
+                            action.name(),
+                            other.name()
+                        ));
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,32 @@
+//! Game simulation for invaders, independent of any terminal or network frontend.
+//!
+//! The terminal frontend lives in the `invaderse` binary. Tools and tests can drive
+//! the game headlessly through [`Simulation`].
+
+pub mod backend;
+pub mod components;
+pub mod input;
+pub mod keymap;
+pub mod render;
+pub mod save;
+pub mod scores;
+pub mod settings;
+pub mod simulation;
+pub mod sprites;
+pub mod state;
+pub mod systems;
+pub mod waves;
+
+pub use crate::backend::*;
+pub use crate::components::*;
+pub use crate::input::*;
+pub use crate::keymap::*;
+pub use crate::render::*;
+pub use crate::save::*;
+pub use crate::scores::*;
+pub use crate::settings::*;
+pub use crate::simulation::*;
+pub use crate::sprites::*;
+pub use crate::state::*;
+pub use crate::systems::*;
+pub use crate::waves::*;
@@ -1,208 +1,226 @@
+use std::backtrace::Backtrace;
 use std::error::Error;
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
 use std::time::{Duration, Instant};
 
-use crossterm::{ExecutableCommand, cursor, event::PopKeyboardEnhancementFlags, terminal};
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
-use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::mpsc;
 
-mod components;
+mod config;
 mod events;
-mod render;
-mod state;
-mod systems;
-use crate::components::*;
+mod net;
+use crate::config::{
+    load_high_scores, load_keymap, load_settings, save_high_scores, write_crash_report,
+};
 use crate::events::*;
-use crate::render::*;
-use crate::state::*;
-use crate::systems::*;
+use crate::net::*;
+use invaderse::*;
+
+/// Reads `--tick-rate <hz>` from the command line, which overrides the settings
+fn parse_tick_rate() -> Result<Option<u32>, Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--tick-rate" {
+            let value = args.next().ok_or("--tick-rate requires a value")?;
+            let tick_rate: u32 = value.parse()?;
+            if tick_rate == 0 {
+                return Err("--tick-rate must be greater than 0".into());
+            }
+            return Ok(Some(tick_rate));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads `--release-timeout <ms>`, used to infer key releases on terminals that
+/// don't report them
+fn parse_release_timeout() -> Result<Option<Duration>, Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--release-timeout" {
+            let value = args.next().ok_or("--release-timeout requires a value")?;
+            return Ok(Some(Duration::from_millis(value.parse()?)));
+        }
+    }
+    Ok(None)
+}
+
+/// Reads `--sprites <braille|ascii|block>`, which overrides the settings
+fn parse_sprite_set() -> Result<Option<SpriteSet>, Box<dyn Error>> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--sprites" {
+            let value = args.next().ok_or("--sprites requires a value")?;
+            let set = SpriteSet::from_name(&value).ok_or_else(|| {
+                format!("unknown sprite set '{value}', use braille, ascii or block")
+            })?;
+            return Ok(Some(set));
+        }
+    }
+    Ok(None)
+}
+
+/// `--mouse` lets the cannon follow the mouse and menu entries be clicked
+fn mouse_enabled() -> bool {
+    std::env::args().skip(1).any(|arg| arg == "--mouse")
+}
+
+/// Loads the waves file given with `--waves <path>`. Invalid files fall back to the
+/// built-in waves, with the problems returned to be shown on screen.
+fn load_waves() -> (WaveSet, Vec<String>) {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--waves" {
+            let Some(path) = args.next() else {
+                return (
+                    WaveSet::builtin(),
+                    vec!["--waves requires a path".to_string()],
+                );
+            };
+            return match WaveSet::load(Path::new(&path)) {
+                Ok(waves) => (waves, Vec::new()),
+                Err(errors) => (WaveSet::builtin(), errors),
+            };
+        }
+    }
+    (WaveSet::builtin(), Vec::new())
+}
+
+/// Leaves the current screen, returns true when quitting from the main menu
+fn handle_quit(game_state: &mut GameState) -> bool {
+    match game_state.main_menu.screen {
+        Screen::Main => return true,
+        // Both lead back to the paused game when opened from its pause menu
+        Screen::Controls => {
+            game_state.main_menu.screen = if game_state.settings_menu.in_game {
+                Screen::Settings
+            } else {
+                Screen::Main
+            };
+            game_state.request_clear_render = true;
+        }
+        Screen::Settings => {
+            game_state.main_menu.screen = if game_state.settings_menu.in_game {
+                Screen::Game
+            } else {
+                Screen::Main
+            };
+            game_state.request_clear_render = true;
+        }
+        Screen::Game if !game_state.game_over => {
+            confirm_in_pause_menu(game_state, PauseItem::QuitToMenu);
+        }
+        Screen::Game | Screen::Hosting | Screen::Joining => game_state.exit_to_menu(),
+    }
+    false
+}
+
+/// Restores the terminal before the panic message is printed, raw mode would garble
+/// it, then writes a crash report. Exits right away since a panicking task would
+/// otherwise leave the game running in a restored terminal.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        let _ = restore_terminal();
+        default_hook(info);
+
+        let report = format!(
+            "invaderse {} crashed in thread '{}'\n\n{info}\n\n{}\n",
+            env!("CARGO_PKG_VERSION"),
+            std::thread::current().name().unwrap_or("<unnamed>"),
+            Backtrace::force_capture()
+        );
+        match write_crash_report(&report) {
+            Ok(path) => eprintln!("Crash report written to {}", path.display()),
+            Err(e) => eprintln!("Could not write a crash report: {e}"),
+        }
+        std::process::exit(101);
+    }));
+}
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn Error>> {
+    install_panic_hook();
+    let result = run().await;
+    // Also reached when `run` bails out with an error halfway through
+    let restored = restore_terminal();
+    result?;
+    Ok(restored?)
+}
+
+async fn run() -> Result<(), Box<dyn Error>> {
+    let tick_rate = parse_tick_rate()?;
+    let release_timeout = parse_release_timeout()?;
+    let sprite_set = parse_sprite_set()?;
+
     // Networking
 
     let (tx, mut rx) = mpsc::unbounded_channel();
 
     spawn_coordination_threads(&tx);
 
-    let (mut game_state, mut renderer) = create_world()?;
-
-    let kb_enhanced = renderer.terminal_raw_mode()?;
-
-    if let Err(e) = renderer.render_main_menu(&mut game_state) {
-        // We drop errors to keep and return the game_state.render() error instead
-        if kb_enhanced {
-            let _ = renderer.stdout.execute(PopKeyboardEnhancementFlags);
-        }
-        let _ = renderer.stdout.execute(cursor::Show);
-        let _ = terminal::disable_raw_mode();
-
-        return Err(e);
+    let (waves, wave_errors) = load_waves();
+    let (settings, settings_errors) = load_settings();
+    let mut game_state = create_world_with_settings(waves, settings);
+    game_state.wave_errors = wave_errors;
+    game_state.settings_errors = settings_errors;
+    game_state.high_scores = load_high_scores();
+    game_state.high_score = game_state.high_scores.get(game_state.difficulty);
+    (game_state.keymap, game_state.keymap_errors) = load_keymap();
+    let mut renderer = Render::new(CrosstermBackend::new())?;
+    renderer.color &= game_state.settings.color;
+    renderer.sprite_set = sprite_set.unwrap_or(game_state.settings.sprite_set);
+    let mut net = NetSession::default();
+
+    let kb_enhanced = renderer.backend.terminal_raw_mode()?;
+    if mouse_enabled() {
+        renderer.backend.capture_mouse()?;
     }
+    let mut key_input = KeyInput::new(kb_enhanced, release_timeout);
 
-    let mut last_frame_time = Instant::now();
-    let max_dt = Duration::from_millis(20); // clamp to avoid speed spikes
-    let fixed_dt = Duration::from_millis(16);
+    renderer.render_main_menu(&mut game_state)?;
 
-    loop {
-        // Block until at least one event arrives
-        let mut tick_pending = false;
-        match rx.recv().await {
-            Some(GameEvent::Quit) => match game_state.main_menu.screen {
-                Screen::Game => {
-                    game_state.exit_to_menu();
-                }
-                Screen::Hosting => {
-                    game_state.exit_to_menu();
-                }
-                Screen::Joining => {
-                    game_state.exit_to_menu();
-                }
-                Screen::Main => {
-                    break;
-                }
-            },
-            Some(event) => tick_pending |= handle_event(event, &mut renderer, &mut game_state),
-            _ => break,
-        };
+    let mut timestep = FixedTimestep::new(tick_rate.unwrap_or(game_state.settings.tick_rate));
 
-        // Drain any queued events; fold multiple ticks into a single step
+    'game: loop {
+        // Block until at least one event arrives, then drain any queued events;
+        // multiple ticks collapse into a single frame
+        let Some(event) = rx.recv().await else {
+            break;
+        };
+        let mut events = vec![event];
         while let Ok(event) = rx.try_recv() {
-            match event {
-                GameEvent::Quit => {
-                    // Exit immediately on quit
-                    renderer.terminal_disable_raw(kb_enhanced)?;
+            events.push(event);
+        }
+        events.extend(key_input.expired());
 
-                    return Ok(());
-                }
-                other => {
-                    tick_pending |= handle_event(other, &mut renderer, &mut game_state);
+        let mut tick_pending = false;
+        for event in events {
+            match map_key(event, &mut game_state, &mut key_input) {
+                Some(GameEvent::Quit) if handle_quit(&mut game_state) => break 'game,
+                Some(GameEvent::Terminate) => break 'game,
+                Some(GameEvent::Quit) | None => (),
+                Some(event) => {
+                    tick_pending |= handle_event(event, &mut renderer, &mut game_state, &mut net);
                 }
             }
         }
 
         if tick_pending {
-            if !game_state.networking.stay_online {
-                if let Some(handle) = game_state.networking.connection_task.take() {
-                    handle.abort();
-                    game_state.networking.connection_task = Option::None;
-                }
-            } else if game_state.networking.connection_task.is_none() {
-                if game_state.networking.host {
-                    let tx_net = tx.clone();
-                    let task = tokio::spawn(async move {
-                        match TcpListener::bind("127.0.0.1:23471").await {
-                            Ok(listener) => {
-                                if let Ok((stream, addr)) = listener.accept().await {
-                                    // let _ = tx_net.send(GameEvent::PeerConnected(addr));
-                                    let (reader, mut writer) = stream.into_split();
-
-                                    let tx_game_events = tx_net.clone();
-
-                                    tokio::spawn(async move {
-                                        let mut reader = tokio::io::BufReader::new(reader);
-                                        let mut len_buf = [0u8; 4];
-
-                                        loop {
-                                            // Read the 4-byte length prefix
-                                            if reader.read_exact(&mut len_buf).await.is_err() {
-                                                break;
-                                            }
-                                            let len = u32::from_be_bytes(len_buf) as usize;
+            // Steps owed since the last frame. They are only simulated while playing,
+            // time spent in menus, pause or game over is discarded.
+            let steps = timestep.advance(Instant::now());
 
-                                            // Read the message body
-                                            let mut msg_buf = vec![0u8; len];
-                                            if reader.read_exact(&mut msg_buf).await.is_err() {
-                                                break;
-                                            }
-
-                                            if let Ok(packet) =
-                                                bincode::deserialize::<NetPacket>(&msg_buf)
-                                            {
-                                                let _ = tx_game_events
-                                                    .send(GameEvent::PacketReceived(packet));
-                                            }
-                                        }
-                                    });
-
-                                    let (tx_outbox, mut rx_outbox) =
-                                        mpsc::unbounded_channel::<NetPacket>();
-
-                                    let _ = tx_net.send(GameEvent::PeerConnected(addr, tx_outbox));
-                                    tokio::spawn(async move {
-                                        while let Some(packet) = rx_outbox.recv().await {
-                                            let bytes = bincode::serialize(&packet).unwrap();
-                                            let len = (bytes.len() as u32).to_be_bytes();
-
-                                            let _ = writer.write_all(&len).await;
-                                            let _ = writer.write_all(&bytes).await;
-                                            let _ = writer.flush().await;
-                                        }
-                                    });
-                                }
-                            }
-                            Err(_) => { /* Handle bind error if necessary */ }
-                        }
-                    });
-                    game_state.networking.connection_task = Some(task);
+            if !game_state.networking.stay_online {
+                net.reset();
+            } else if net.connection_task.is_none() {
+                let task = if game_state.networking.host {
+                    spawn_host(game_state.settings.host_port, tx.clone())
                 } else {
-                    let tx_net = tx.clone();
-                    let task = tokio::spawn(async move {
-                        let addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 23471);
-                        match TcpStream::connect(addr).await {
-                            Ok(stream) => {
-                                let (reader, mut writer) = stream.into_split();
-
-                                let tx_game_events = tx_net.clone();
-
-                                tokio::spawn(async move {
-                                    let mut reader = tokio::io::BufReader::new(reader);
-                                    let mut len_buf = [0u8; 4];
-
-                                    loop {
-                                        // Read the 4-byte length prefix
-                                        if reader.read_exact(&mut len_buf).await.is_err() {
-                                            break;
-                                        }
-                                        let len = u32::from_be_bytes(len_buf) as usize;
-
-                                        // Read the message body
-                                        let mut msg_buf = vec![0u8; len];
-                                        if reader.read_exact(&mut msg_buf).await.is_err() {
-                                            break;
-                                        }
-
-                                        if let Ok(packet) =
-                                            bincode::deserialize::<NetPacket>(&msg_buf)
-                                        {
-                                            let _ = tx_game_events
-                                                .send(GameEvent::PacketReceived(packet));
-                                        }
-                                    }
-                                });
-
-                                let (tx_outbox, mut rx_outbox) =
-                                    mpsc::unbounded_channel::<NetPacket>();
-
-                                let _ = tx_net.send(GameEvent::PeerConnected(addr, tx_outbox));
-                                tokio::spawn(async move {
-                                    while let Some(packet) = rx_outbox.recv().await {
-                                        let bytes = bincode::serialize(&packet).unwrap();
-                                        let len = (bytes.len() as u32).to_be_bytes();
-
-                                        let _ = writer.write_all(&len).await;
-                                        let _ = writer.write_all(&bytes).await;
-                                        let _ = writer.flush().await;
-                                    }
-                                });
-                            }
-                            Err(_) => { /* Handle bind error if necessary */ }
-                        }
-                    });
-                    game_state.networking.connection_task = Some(task);
-                }
+                    spawn_join(game_state.settings.join_address, tx.clone())
+                };
+                net.connection_task = Some(task);
             }
+            game_state.networking.listening = net.connection_task.is_some();
 
             match game_state.main_menu.screen {
                 Screen::Hosting => {
@@ -217,60 +235,76 @@ async fn main() -> Result<(), Box<dyn Error>> {
                     renderer.render_join_menu(&mut game_state)?;
                     continue;
                 }
+                Screen::Controls => {
+                    renderer.render_controls_menu(&mut game_state)?;
+                    continue;
+                }
+                Screen::Settings => {
+                    renderer.render_settings_menu(&mut game_state)?;
+                    continue;
+                }
                 _ => (),
             }
 
             if game_state.restart_notifier {
-                (game_state, renderer) = restart_world(game_state.high_score)?;
+                let keymap = game_state.keymap.clone();
+                let local_coop = game_state.local_coop;
+                let high_scores = game_state.high_scores;
+                let waves = game_state.waves.clone();
+                let settings = game_state.settings.clone();
+                game_state = match game_state.resume.take() {
+                    Some(save) => {
+                        let mut resumed =
+                            resume_world(high_scores.get(save.difficulty), waves, settings, &save);
+                        resumed.local_coop = save.local_coop;
+                        resumed
+                    }
+                    None => {
+                        let mut restarted =
+                            restart_world(high_scores.get(settings.difficulty), waves, settings);
+                        restarted.local_coop = local_coop;
+                        restarted
+                    }
+                };
+                game_state.keymap = keymap;
+                game_state.high_scores = high_scores;
+                renderer = Render::new(CrosstermBackend::new())?;
+                renderer.color &= game_state.settings.color;
+                renderer.sprite_set = sprite_set.unwrap_or(game_state.settings.sprite_set);
+                timestep = FixedTimestep::new(tick_rate.unwrap_or(game_state.settings.tick_rate));
+                net.reset();
                 continue;
             }
 
             if game_state.pause_notifier {
-                // renderer.render(&mut game_state)?;
-                if game_state.paused {
-                    game_state.paused = false;
-                    renderer.erase_pause()?;
-                } else {
-                    game_state.paused = true;
-                    renderer.draw_pause()?;
-                }
+                game_state.set_paused(!game_state.paused);
                 game_state.pause_notifier = false;
             }
 
-            if game_state.game_over_notifier {
-                if game_state.game_over {
-                    game_state.game_over = false;
-                    renderer.erase_game_over()?;
-                } else {
-                    game_state.game_over = true;
-                    renderer.draw_menu_items(
-                        game_state.score,
-                        game_state.high_score,
-                        game_state.player_lives,
-                        false,
-                    )?;
-                    renderer.draw_game_over(game_state.score, game_state.high_score)?;
+            if !game_state.game_over && !game_state.paused {
+                for _ in 0..steps {
+                    if game_state.networking.connected {
+                        process_multiplayer(timestep.step, &mut game_state)?;
+                    } else {
+                        process_tick(timestep.step, &mut game_state)?;
+                    }
 
-                    if game_state.score > game_state.high_score {
-                        game_state.high_score = game_state.score;
+                    if game_state.game_over_notifier {
+                        break;
                     }
                 }
-                game_state.game_over_notifier = false;
             }
-            if game_state.game_over || game_state.paused {
-                continue;
+            for packet in game_state.networking.outbox.drain(..) {
+                net.send(packet)?;
             }
 
-            let now = Instant::now();
-            let mut dt = now.duration_since(last_frame_time);
-            last_frame_time = now;
-            // Clamp dt to reduce perceived speed changes when we fall behind
-            dt = dt.min(max_dt);
-
-            if game_state.networking.tx_writer.is_some() {
-                process_multiplayer(dt.max(fixed_dt).min(max_dt), &mut game_state)?;
-            } else {
-                process_tick(dt.max(fixed_dt).min(max_dt), &mut game_state)?;
+            if game_state.game_over_notifier {
+                game_state.game_over_notifier = false;
+                game_state.end_game();
+                // A failed save only loses the record, the game goes on
+                if game_state.new_high_score {
+                    let _ = save_high_scores(&game_state.high_scores);
+                }
             }
 
             match renderer.render(&mut game_state) {
@@ -282,7 +316,5 @@ async fn main() -> Result<(), Box<dyn Error>> {
         }
     }
 
-    // Disable keyboard enhancement (if enabled), show cursor again, and disable raw mode before exiting
-    renderer.terminal_disable_raw(kb_enhanced)?;
     Ok(())
 }
@@ -0,0 +1,103 @@
+use std::net::SocketAddr;
+
+use invaderse::NetPacket;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::events::GameEvent;
+
+/// Tokio handles backing `GameState.networking`, owned by the terminal frontend
+#[derive(Default)]
+pub struct NetSession {
+    pub connection_task: Option<JoinHandle<()>>,
+    pub tx_writer: Option<mpsc::UnboundedSender<NetPacket>>,
+}
+
+impl NetSession {
+    pub fn reset(&mut self) {
+        if let Some(handle) = self.connection_task.take() {
+            handle.abort();
+        }
+        self.tx_writer = Option::None;
+    }
+
+    pub fn send(&self, packet: NetPacket) -> Result<(), mpsc::error::SendError<NetPacket>> {
+        match self.tx_writer {
+            Some(ref tx_writer) => tx_writer.send(packet),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Listens for a single peer and wires its stream to the game event channel
+pub fn spawn_host(port: u16, tx_net: mpsc::UnboundedSender<GameEvent>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        match TcpListener::bind(("127.0.0.1", port)).await {
+            Ok(listener) => {
+                if let Ok((stream, addr)) = listener.accept().await {
+                    spawn_peer_io(stream, addr, tx_net);
+                }
+            }
+            Err(_) => { /* Handle bind error if necessary */ }
+        }
+    })
+}
+
+/// Connects to the host and wires its stream to the game event channel
+pub fn spawn_join(addr: SocketAddr, tx_net: mpsc::UnboundedSender<GameEvent>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        match TcpStream::connect(addr).await {
+            Ok(stream) => spawn_peer_io(stream, addr, tx_net),
+            Err(_) => { /* Handle bind error if necessary */ }
+        }
+    })
+}
+
+fn spawn_peer_io(stream: TcpStream, addr: SocketAddr, tx_net: mpsc::UnboundedSender<GameEvent>) {
+    let (reader, mut writer) = stream.into_split();
+
+    let tx_game_events = tx_net.clone();
+
+    tokio::spawn(async move {
+        let mut reader = tokio::io::BufReader::new(reader);
+        let mut len_buf = [0u8; 4];
+
+        loop {
+            // Read the 4-byte length prefix
+            if reader.read_exact(&mut len_buf).await.is_err() {
+                break;
+            }
+            let len = u32::from_be_bytes(len_buf) as usize;
+
+            // Read the message body
+            let mut msg_buf = vec![0u8; len];
+            if reader.read_exact(&mut msg_buf).await.is_err() {
+                break;
+            }
+
+            if let Ok(packet) = bincode::deserialize::<NetPacket>(&msg_buf) {
+                let _ = tx_game_events.send(GameEvent::PacketReceived(packet));
+            }
+        }
+    });
+
+    let (tx_outbox, mut rx_outbox) = mpsc::unbounded_channel::<NetPacket>();
+
+    let _ = tx_net.send(GameEvent::PeerConnected(addr, tx_outbox));
+    tokio::spawn(async move {
+        while let Some(packet) = rx_outbox.recv().await {
+            // Like undecodable packets on the reading side, a packet that fails to
+            // encode is dropped rather than taking the connection down
+            let Ok(bytes) = bincode::serialize(&packet) else {
+                continue;
+            };
+            let len = (bytes.len() as u32).to_be_bytes();
+
+            let _ = writer.write_all(&len).await;
+            let _ = writer.write_all(&bytes).await;
+            let _ = writer.flush().await;
+        }
+    });
+}
@@ -1,486 +1,1038 @@
 use std::error::Error;
-use std::io::{Stdout, Write};
-
-use crossterm::terminal::WindowSize;
-use crossterm::{
-    ExecutableCommand, cursor,
-    event::{KeyboardEnhancementFlags, PopKeyboardEnhancementFlags, PushKeyboardEnhancementFlags},
-    queue,
-    terminal::{self, Clear, ClearType},
+
+use crate::backend::Backend;
+use crate::{
+    Action, BOSS_PARTS, BOSS_STYLE, BOSS_WEAK_POINT_STYLE, BUNKER_STYLE, Boss, COPLAYER_STYLE,
+    Color, Difficulty, ENEMY_PROJECTILE_STYLE, ENEMY_SHOT_SPRITE, Effect, EffectKind, EnemyKind,
+    FloatingText, GameState, Key, Keymap, MenuItem, NET_BOSS_HEALTH, NET_BOSS_PART, NET_COPLAYER,
+    NET_COPLAYER_PROJECTILE, NET_ENEMY_PROJECTILE, NET_FORMATION_FRAME, NET_PLAYER,
+    NET_PLAYER_PROJECTILE, NET_UFO, PLAYER_SHOT_SPRITE, PLAYER_SPRITE, PLAYER_STYLE, PauseItem,
+    PauseMenu, Position, PowerUpKind, PowerUps, Renderable, SCREEN_HEIGHT, SCREEN_WIDTH, Screen,
+    SettingsItem, Sprite, SpriteSet, Style, UFO_SPRITE, UFO_STYLE, bunker_sprite,
 };
 
-use crate::{GameState, MenuItem, Position, PrevPosition, Renderable, SCREEN_HEIGHT, SCREEN_WIDTH};
+const HINT_STYLE: Style = Style::fg(Color::DarkGrey);
+const SCORE_STYLE: Style = Style::fg(Color::Yellow);
+const HIGH_SCORE_STYLE: Style = Style::fg(Color::Cyan);
+const LIVES_STYLE: Style = Style::fg(Color::Red);
+const SELECTED_STYLE: Style = Style::fg(Color::Yellow).bold();
+const PAUSE_STYLE: Style = Style::fg(Color::Yellow).bold();
+const GAME_OVER_STYLE: Style = Style::fg(Color::Red).bold();
+const WAVE_BANNER_STYLE: Style = Style::fg(Color::Cyan).bold();
+const ERROR_STYLE: Style = Style::fg(Color::Red);
+const BOSS_HEALTH_STYLE: Style = Style::fg(Color::Magenta).bold();
+const BOSS_HEALTH_BAR_WIDTH: usize = 40;
+/// Config file problems listed on the main menu, the rest is summarized
+const MAX_ERROR_LINES: usize = 10;
+/// Columns taken by each main menu entry, including its separator
+pub const MENU_ENTRY_WIDTH: u16 = 15;
+/// Rows between the buttons on the Hosting and Joining screens and the bottom wall
+const BUTTON_ROW: u16 = 17;
+/// Smallest terminal the frame is shrunk into, the HUD line no longer fits below it
+pub const MIN_COLUMNS: u16 = 80;
+pub const MIN_ROWS: u16 = 24;
+
+/// Menu entry that can be picked with the mouse
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEntry {
+    Item(MenuItem),
+    /// Starts the hosted game once someone joined
+    Start,
+    /// Leaves the Hosting or Joining screen
+    Back,
+}
 
-pub struct Render {
-    pub wsize_updated: bool,
-    pub stdout: Stdout,
-    pub wsize: WindowSize,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cell {
+    pub ch: char,
+    pub style: Style,
+    /// Sprite cells fill their whole block when the field is scaled up, text is
+    /// kept one cell per character
+    pub sprite: bool,
 }
 
-impl Render {
-    pub fn render(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
-        let (left, _, _, bottom) = self.get_game_bounds();
+const BLANK: Cell = Cell {
+    ch: ' ',
+    style: Style::fg(Color::Default),
+    sprite: false,
+};
 
-        if self.wsize.rows < SCREEN_HEIGHT + 5 || self.wsize.columns < SCREEN_WIDTH + 5 {
-            queue!(self.stdout, Clear(ClearType::All))?;
-            queue!(self.stdout, cursor::MoveTo(0, 0))?;
-            write!(self.stdout, "Terminal too small")?;
-            return Ok(());
+/// Grid of terminal cells a frame is composed into before it is presented
+pub struct FrameBuffer {
+    columns: u16,
+    rows: u16,
+    cells: Vec<Cell>,
+}
+
+impl FrameBuffer {
+    pub fn new(columns: u16, rows: u16) -> Self {
+        Self {
+            columns,
+            rows,
+            cells: vec![BLANK; columns as usize * rows as usize],
         }
+    }
 
-        if self.wsize_updated || game_state.request_clear_render {
-            game_state.request_clear_render = false;
-            self.wsize_updated = false;
+    pub fn size(&self) -> (u16, u16) {
+        (self.columns, self.rows)
+    }
 
-            self.render_borders()?;
-            self.draw_menu_items(
-                game_state.score,
-                game_state.high_score,
-                game_state.player_lives,
-                game_state.paused,
-            )?;
+    pub fn get(&self, x: u16, y: u16) -> Option<Cell> {
+        if x < self.columns && y < self.rows {
+            Some(self.cells[y as usize * self.columns as usize + x as usize])
+        } else {
+            None
         }
+    }
 
-        if game_state.score_updated {
-            game_state.score_updated = false;
-            self.draw_menu_items(
-                game_state.score,
-                game_state.high_score,
-                game_state.player_lives,
-                false,
-            )?;
-        }
+    /// Writes the string starting at `(x, y)` in the default style
+    pub fn put_str(&mut self, x: u16, y: u16, s: &str) {
+        self.put_styled(x, y, s, Style::default());
+    }
 
-        if game_state.paused {
-            self.draw_menu_items(
-                game_state.score,
-                game_state.high_score,
-                game_state.player_lives,
-                game_state.paused,
-            )?;
-            return Ok(());
+    /// Writes the string starting at `(x, y)`, clipping anything outside the buffer
+    pub fn put_styled(&mut self, x: u16, y: u16, s: &str, style: Style) {
+        self.put(x, y, s, style, false);
+    }
+
+    /// Writes sprite glyphs starting at `(x, y)`, see [`Cell::sprite`]
+    pub fn put_sprite(&mut self, x: u16, y: u16, s: &str, style: Style) {
+        self.put(x, y, s, style, true);
+    }
+
+    fn put(&mut self, x: u16, y: u16, s: &str, style: Style, sprite: bool) {
+        if y >= self.rows {
+            return;
         }
+        for (i, ch) in s.chars().enumerate() {
+            let cx = x as usize + i;
+            if cx >= self.columns as usize {
+                break;
+            }
+            self.cells[y as usize * self.columns as usize + cx] = Cell { ch, style, sprite };
+        }
+    }
 
-        if game_state.networking.host || !game_state.networking.stay_online {
-            for (_id, (pos, prev_pos, renderable)) in
-                game_state
-                    .world
-                    .query_mut::<(&Position, &PrevPosition, &mut Renderable)>()
-            {
-                self.draw_entity(left, bottom, pos, prev_pos, renderable)?;
-                if renderable.destroy {
-                    renderable.erased = true;
+    /// Scales the buffer up `scale` times into one of `columns` by `rows`. Sprite
+    /// cells become `scale` by `scale` blocks, words of text keep their letters
+    /// together and start where their first letter scales to.
+    pub fn scaled(&self, scale: u16, columns: u16, rows: u16) -> FrameBuffer {
+        let mut scaled = FrameBuffer::new(columns, rows);
+        for y in 0..self.rows {
+            let mut word_start = None;
+            for x in 0..self.columns {
+                let cell = self.cells[y as usize * self.columns as usize + x as usize];
+                if cell.sprite {
+                    word_start = None;
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            scaled.set(x * scale + dx, y * scale + dy, cell);
+                        }
+                    }
+                } else if cell == BLANK {
+                    word_start = None;
+                } else {
+                    let start = *word_start.get_or_insert(x);
+                    scaled.set(start * scale + x - start, y * scale, cell);
                 }
             }
-        } else if let Some(ref entities) = game_state.coplayer_handler.old_host_entities {
-            for (code, x, y) in entities.clone() {
-                if code == 0 {
-                    self.draw_entity(
-                        left,
-                        bottom,
-                        &Position { x, y },
-                        &PrevPosition { x, y },
-                        &Renderable {
-                            sprite_top: "     ",
-                            sprite_bottom: "     ",
-                            width: 5,
-                            destroy: false,
-                            erased: false,
-                        },
-                    )?;
-                } else if code == 1 {
-                    self.draw_entity(
-                        left,
-                        bottom,
-                        &Position { x, y },
-                        &PrevPosition { x, y },
-                        &Renderable {
-                            sprite_top: " ",
-                            sprite_bottom: " ",
-                            width: 1,
-                            destroy: false,
-                            erased: false,
-                        },
-                    )?;
-                } else if code == 2 {
-                    self.draw_entity(
-                        left,
-                        bottom,
-                        &Position { x, y },
-                        &PrevPosition { x, y },
-                        &Renderable {
-                            sprite_top: "     ",
-                            sprite_bottom: "     ",
-                            width: 5,
-                            destroy: false,
-                            erased: false,
-                        },
-                    )?;
+        }
+        scaled
+    }
+
+    /// Shrinks the buffer into a smaller one of `columns` by `rows`. Each sprite cell
+    /// takes the first sprite cell of the block that maps onto it. Text stays legible:
+    /// words keep their letters together, and words or rows of text that would run
+    /// into each other are pushed along or down.
+    pub fn shrunk(&self, columns: u16, rows: u16) -> FrameBuffer {
+        let mut shrunk = FrameBuffer::new(columns, rows);
+        let map = |v: u16, from: u16, to: u16| (v as u32 * to as u32 / from as u32) as u16;
+
+        for y in 0..self.rows {
+            for x in 0..self.columns {
+                let cell = self.cells[y as usize * self.columns as usize + x as usize];
+                let (tx, ty) = (map(x, self.columns, columns), map(y, self.rows, rows));
+                if cell.sprite && shrunk.get(tx, ty) == Some(BLANK) {
+                    shrunk.set(tx, ty, cell);
                 }
             }
         }
 
-        if let Some(ref entities) = game_state.coplayer_handler.host_entities {
-            for (code, x, y) in entities.clone() {
-                if code == 0 {
-                    self.draw_entity(
-                        left,
-                        bottom,
-                        &Position { x, y },
-                        &PrevPosition { x, y },
-                        &Renderable {
-                            sprite_top: "⢳⡴⠶⢦⡞",
-                            sprite_bottom: "⠞⠫⡪⠋⠱",
-                            width: 5,
-                            destroy: false,
-                            erased: false,
-                        },
-                    )?;
-                } else if code == 1 {
-                    self.draw_entity(
-                        left,
-                        bottom,
-                        &Position { x, y },
-                        &PrevPosition { x, y },
-                        &Renderable {
-                            sprite_top: "⣿",
-                            sprite_bottom: " ",
-                            width: 1,
-                            destroy: false,
-                            erased: false,
-                        },
-                    )?;
-                } else if code == 2 {
-                    self.draw_entity(
-                        left,
-                        bottom,
-                        &Position { x, y },
-                        &PrevPosition { x, y },
-                        &Renderable {
-                            sprite_top: "⣆⡜⣛⢣⣠",
-                            sprite_bottom: "⣿⣿⣿⣿⣿",
-                            width: 5,
-                            destroy: false,
-                            erased: false,
-                        },
-                    )?;
+        let mut next_row = 0;
+        for y in 0..self.rows {
+            let row = &self.cells[y as usize * self.columns as usize..][..self.columns as usize];
+            // Spaces of any style separate words
+            let is_text = |cell: &Cell| !cell.sprite && cell.ch != ' ';
+            if !row.iter().any(is_text) {
+                continue;
+            }
+            let ty = map(y, self.rows, rows).max(next_row);
+            next_row = ty + 1;
+
+            let mut next_column = 0;
+            let mut word_column = None;
+            for (x, cell) in (0..).zip(row) {
+                if !is_text(cell) {
+                    if word_column.take().is_some() {
+                        // Keep a space after each word
+                        next_column += 1;
+                    }
+                    continue;
                 }
+                let tx = *word_column
+                    .get_or_insert_with(|| map(x, self.columns, columns).max(next_column));
+                shrunk.set(tx, ty, *cell);
+                word_column = Some(tx + 1);
+                next_column = tx + 1;
             }
         }
+        shrunk
+    }
 
-        self.stdout.flush()?;
+    fn set(&mut self, x: u16, y: u16, cell: Cell) {
+        if x < self.columns && y < self.rows {
+            self.cells[y as usize * self.columns as usize + x as usize] = cell;
+        }
+    }
 
-        Ok(())
+    pub fn clear(&mut self) {
+        self.cells.fill(BLANK);
     }
+}
 
-    pub fn render_main_menu(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
-        let (left, _, _, bottom) = self.get_game_bounds();
+/// Composes every frame into a back buffer and sends only the cells that
+/// changed since the previously presented frame to the backend
+pub struct Render<B: Backend> {
+    pub wsize_updated: bool,
+    pub backend: B,
+    pub columns: u16,
+    pub rows: u16,
+    /// When false every cell is presented without colors
+    pub color: bool,
+    pub sprite_set: SpriteSet,
+    back: FrameBuffer,
+    front: FrameBuffer,
+}
+
+impl<B: Backend> Render<B> {
+    /// Times the play field is scaled up, the largest that fits the terminal
+    pub fn scale(&self) -> u16 {
+        (self.columns / (SCREEN_WIDTH + 5))
+            .min(self.rows / (SCREEN_HEIGHT + 5))
+            .max(1)
+    }
+
+    /// Size of the unscaled frame every screen is composed into, terminals smaller
+    /// than the play field get the frame shrunk to fit
+    fn canvas_size(&self) -> (u16, u16) {
+        (
+            (self.columns / self.scale()).max(SCREEN_WIDTH + 5),
+            (self.rows / self.scale()).max(SCREEN_HEIGHT + 5),
+        )
+    }
 
-        if self.wsize.rows < SCREEN_HEIGHT + 5 || self.wsize.columns < SCREEN_WIDTH + 5 {
-            queue!(self.stdout, Clear(ClearType::All))?;
-            queue!(self.stdout, cursor::MoveTo(0, 0))?;
-            write!(self.stdout, "Terminal too small")?;
-            return Ok(());
+    /// Whether the terminal row shows the row of the unscaled frame. Several rows of a
+    /// shrunk frame share a terminal row, so this is not the reverse of `canvas_cell`.
+    fn shows_row(&self, row: u16, canvas_row: u16) -> bool {
+        let (_, rows) = self.canvas_size();
+        if rows > self.rows {
+            (canvas_row as u32 * self.rows as u32 / rows as u32) as u16 == row
+        } else {
+            row / self.scale() == canvas_row
         }
+    }
 
-        if self.wsize_updated || game_state.request_clear_render {
-            game_state.request_clear_render = false;
-            self.wsize_updated = false;
+    /// Whether the frame is shrunk horizontally to fit the terminal
+    fn shrinks_columns(&self) -> bool {
+        self.canvas_size().0 > self.columns
+    }
 
-            self.render_borders()?;
-            self.draw_menu_items(
-                game_state.score,
-                game_state.high_score,
-                game_state.player_lives,
-                game_state.paused,
-            )?;
+    /// Cell of the unscaled frame shown at the terminal cell
+    fn canvas_cell(&self, column: u16, row: u16) -> (u16, u16) {
+        let (columns, rows) = self.canvas_size();
+        let unmap = |v: u16, terminal: u16, canvas: u16| {
+            if canvas > terminal {
+                // First cell of the block shrunk into `v`
+                (v as u32 * canvas as u32).div_ceil(terminal as u32) as u16
+            } else {
+                v / self.scale()
+            }
+        };
+        (
+            unmap(column, self.columns, columns),
+            unmap(row, self.rows, rows),
+        )
+    }
+
+    pub fn new(backend: B) -> Result<Self, Box<dyn Error>> {
+        let (columns, rows) = backend.size()?;
+        Ok(Render {
+            color: backend.supports_color(),
+            sprite_set: SpriteSet::Braille,
+            backend,
+            columns,
+            rows,
+            wsize_updated: true,
+            back: FrameBuffer::new(columns, rows),
+            front: FrameBuffer::new(columns, rows),
+        })
+    }
+
+    pub fn resize(&mut self, columns: u16, rows: u16) {
+        self.columns = columns;
+        self.rows = rows;
+        self.wsize_updated = true;
+    }
+
+    pub fn render(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+        if !self.begin_frame(game_state) {
+            return self.present(game_state);
         }
 
-        queue!(self.stdout, cursor::MoveTo(left + 35, bottom - 20))?;
-        match game_state.main_menu.active_menu_item {
-            MenuItem::HostGame => {
-                write!(self.stdout, " > HostGame   |   JoinGame   |   PlaySolo   ")?;
+        let (left, _, _, bottom) = self.get_game_bounds();
+
+        if game_state.networking.host || !game_state.networking.stay_online {
+            for (_id, (pos, renderable)) in game_state
+                .world
+                .query_mut::<(&Position, &Renderable)>()
+                .without::<&Effect>()
+            {
+                self.draw_entity(left, bottom, pos, renderable);
             }
-            MenuItem::JoinGame => {
-                write!(self.stdout, "   HostGame   | > JoinGame   |   PlaySolo   ")?;
+            // Explosions go on top of whatever they cover
+            for (_id, (pos, renderable)) in game_state
+                .world
+                .query_mut::<(&Position, &Renderable)>()
+                .with::<&Effect>()
+            {
+                self.draw_entity(left, bottom, pos, renderable);
+            }
+            for (_id, (pos, text)) in game_state.world.query_mut::<(&Position, &FloatingText)>() {
+                self.back
+                    .put_styled(left + pos.x, bottom - pos.y, &text.text, text.style);
+            }
+            if let Ok(power_ups) = game_state
+                .world
+                .query_one_mut::<&PowerUps>(game_state.player_entity)
+            {
+                self.draw_power_ups(power_ups);
+            }
+            for (_id, boss) in game_state.world.query_mut::<&Boss>() {
+                self.draw_boss_health(boss.hp, boss.max_hp);
             }
-            MenuItem::PlaySolo => {
-                write!(self.stdout, "   HostGame   |   JoinGame   | > PlaySolo   ")?;
+        }
+
+        if let Some(ref entities) = game_state.coplayer_handler.host_entities {
+            let formation_frame = entities
+                .iter()
+                .find(|(code, ..)| *code == NET_FORMATION_FRAME)
+                .map_or(0, |&(_, frame, _)| frame as usize % 2);
+
+            for &(code, x, y) in entities {
+                let (sprite_top, sprite_bottom, width, style) = match code {
+                    NET_FORMATION_FRAME => continue,
+                    NET_BOSS_HEALTH => {
+                        // Carries the health instead of a position
+                        self.draw_boss_health(x, y);
+                        continue;
+                    }
+                    code if (NET_BOSS_PART..NET_BOSS_PART + BOSS_PARTS.len() as u16)
+                        .contains(&code) =>
+                    {
+                        let (_, _, sprite_top, sprite_bottom, weak_point) =
+                            BOSS_PARTS[(code - NET_BOSS_PART) as usize];
+                        let style = if weak_point {
+                            BOSS_WEAK_POINT_STYLE
+                        } else {
+                            BOSS_STYLE
+                        };
+                        (sprite_top, sprite_bottom, 5, style)
+                    }
+                    NET_ENEMY_PROJECTILE => {
+                        (Sprite::EMPTY, ENEMY_SHOT_SPRITE, 1, ENEMY_PROJECTILE_STYLE)
+                    }
+                    NET_PLAYER => (PLAYER_SPRITE.0, PLAYER_SPRITE.1, 5, PLAYER_STYLE),
+                    NET_PLAYER_PROJECTILE => (PLAYER_SHOT_SPRITE, Sprite::EMPTY, 1, PLAYER_STYLE),
+                    NET_COPLAYER => (PLAYER_SPRITE.0, PLAYER_SPRITE.1, 5, COPLAYER_STYLE),
+                    NET_COPLAYER_PROJECTILE => {
+                        (PLAYER_SHOT_SPRITE, Sprite::EMPTY, 1, COPLAYER_STYLE)
+                    }
+                    NET_UFO => (UFO_SPRITE, Sprite::EMPTY, 6, UFO_STYLE),
+                    _ => match (
+                        EnemyKind::from_net_code(code),
+                        PowerUpKind::from_net_code(code),
+                        EffectKind::from_net_code(code),
+                    ) {
+                        (Some(kind), ..) => {
+                            let stats = kind.stats();
+                            let (sprite_top, sprite_bottom) = stats.frames[formation_frame];
+                            (sprite_top, sprite_bottom, 5, stats.style)
+                        }
+                        (_, Some(kind), _) => (kind.sprite(), Sprite::EMPTY, 1, kind.style()),
+                        (.., Some((kind, frame))) => {
+                            let (sprite_top, sprite_bottom) = kind.frames()[frame];
+                            (sprite_top, sprite_bottom, kind.width(), kind.style())
+                        }
+                        _ => continue,
+                    },
+                };
+                self.draw_entity(
+                    left,
+                    bottom,
+                    &Position { x, y },
+                    &Renderable {
+                        sprite_top,
+                        sprite_bottom,
+                        width,
+                        style,
+                    },
+                );
             }
         }
 
-        self.stdout.flush()?;
+        for &(x, y, hp) in &game_state.coplayer_handler.host_bunkers {
+            let sprite = bunker_sprite(hp).glyphs(self.sprite_set);
+            self.back
+                .put_sprite(left + x, bottom - y, sprite, BUNKER_STYLE);
+        }
 
-        Ok(())
+        if game_state.game_over {
+            self.draw_game_over(
+                game_state.score,
+                game_state.new_high_score,
+                game_state.difficulty,
+                &game_state.keymap,
+            );
+        } else if game_state.paused {
+            self.draw_pause(&game_state.pause_menu, &game_state.keymap);
+        } else if game_state.wave_banner_timer > 0.0 {
+            self.draw_wave_banner(game_state.wave, game_state.boss_wave);
+        }
+
+        self.present(game_state)
     }
 
-    pub fn render_host_menu(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+    pub fn render_main_menu(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+        if !self.begin_frame(game_state) {
+            return self.present(game_state);
+        }
+
         let (left, _, _, bottom) = self.get_game_bounds();
 
-        if self.wsize.rows < SCREEN_HEIGHT + 5 || self.wsize.columns < SCREEN_WIDTH + 5 {
-            queue!(self.stdout, Clear(ClearType::All))?;
-            queue!(self.stdout, cursor::MoveTo(0, 0))?;
-            write!(self.stdout, "Terminal too small")?;
-            return Ok(());
+        let menu_left = main_menu_left(left);
+        for (index, item) in MenuItem::ALL.into_iter().enumerate() {
+            let x = menu_left + index as u16 * MENU_ENTRY_WIDTH;
+            if item == game_state.main_menu.active_menu_item {
+                let entry = format!(" > {:<11}", item.label());
+                self.back.put_styled(x, bottom - 20, &entry, SELECTED_STYLE);
+            } else {
+                self.back
+                    .put_str(x, bottom - 20, &format!("   {:<11}", item.label()));
+            }
+            // Shrunk frames leave the separators out, the entries don't fit otherwise
+            if index + 1 < MenuItem::ALL.len() && !self.shrinks_columns() {
+                self.back
+                    .put_str(x + MENU_ENTRY_WIDTH - 1, bottom - 20, "|");
+            }
         }
 
-        if self.wsize_updated || game_state.request_clear_render {
-            game_state.request_clear_render = false;
-            self.wsize_updated = false;
+        let difficulty = game_state.settings.difficulty;
+        self.back.put_styled(
+            menu_left,
+            bottom - 18,
+            &format!(
+                "Difficulty - {} | high score - {} | change it in Settings",
+                difficulty.label(),
+                game_state.high_scores.get(difficulty)
+            ),
+            HINT_STYLE,
+        );
+
+        if let Some(ref message) = game_state.main_menu.message {
+            self.back
+                .put_styled(menu_left, bottom - 17, message, ERROR_STYLE);
+        }
 
-            self.render_borders()?;
-            self.draw_menu_items(
-                game_state.score,
-                game_state.high_score,
-                game_state.player_lives,
-                game_state.paused,
-            )?;
+        let mut y = bottom - 16;
+        if !game_state.wave_errors.is_empty() {
+            y = self.draw_errors(
+                y,
+                "Waves file rejected, playing the built-in waves:",
+                &game_state.wave_errors,
+            ) + 1;
+        }
+        if !game_state.keymap_errors.is_empty() {
+            y = self.draw_errors(
+                y,
+                "Key bindings rejected, using the defaults:",
+                &game_state.keymap_errors,
+            ) + 1;
+        }
+        if !game_state.settings_errors.is_empty() {
+            self.draw_errors(
+                y,
+                "Settings rejected, using the defaults:",
+                &game_state.settings_errors,
+            );
         }
 
-        queue!(self.stdout, cursor::MoveTo(left + 35, bottom - 21))?;
-        write!(self.stdout, "HOSTING")?;
-        queue!(self.stdout, cursor::MoveTo(left + 35, bottom - 20))?;
-        match game_state.networking.connection_task {
-            Option::Some(_) => {
-                write!(self.stdout, "Listening...")?;
-            }
-            Option::None => {
-                write!(self.stdout, "Listening Broken")?;
-            }
+        self.present(game_state)
+    }
+
+    pub fn render_host_menu(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+        if !self.begin_frame(game_state) {
+            return self.present(game_state);
+        }
+
+        let (left, _, _, bottom) = self.get_game_bounds();
+
+        self.back.put_str(left + 35, bottom - 21, "HOSTING");
+        if game_state.networking.listening {
+            self.back.put_str(left + 35, bottom - 20, "Listening...");
+        } else {
+            self.back
+                .put_str(left + 35, bottom - 20, "Listening Broken");
         }
 
-        queue!(self.stdout, cursor::MoveTo(left + 35, bottom - 19))?;
         match game_state.networking.peer {
             Option::Some(_) => {
-                write!(self.stdout, "Connected            ")?;
+                self.back.put_str(left + 35, bottom - 19, "Connected");
             }
             Option::None => {
-                write!(self.stdout, "No one joined yet...")?;
+                self.back
+                    .put_str(left + 35, bottom - 19, "No one joined yet...");
             }
         }
+        self.draw_buttons(game_state);
 
-        self.stdout.flush()?;
-
-        Ok(())
+        self.present(game_state)
     }
-    pub fn render_join_menu(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
-        let (left, _, _, bottom) = self.get_game_bounds();
 
-        if self.wsize.rows < SCREEN_HEIGHT + 5 || self.wsize.columns < SCREEN_WIDTH + 5 {
-            queue!(self.stdout, Clear(ClearType::All))?;
-            queue!(self.stdout, cursor::MoveTo(0, 0))?;
-            write!(self.stdout, "Terminal too small")?;
-            return Ok(());
+    pub fn render_join_menu(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+        if !self.begin_frame(game_state) {
+            return self.present(game_state);
         }
 
-        if self.wsize_updated || game_state.request_clear_render {
-            game_state.request_clear_render = false;
-            self.wsize_updated = false;
+        let (left, _, _, bottom) = self.get_game_bounds();
 
-            self.render_borders()?;
-            self.draw_menu_items(
-                game_state.score,
-                game_state.high_score,
-                game_state.player_lives,
-                game_state.paused,
-            )?;
+        self.back.put_str(left + 35, bottom - 21, "JOINING");
+        if game_state.networking.listening {
+            self.back.put_str(left + 35, bottom - 20, "Joining...");
+        } else {
+            self.back.put_str(left + 35, bottom - 20, "Joining Broken");
         }
 
-        queue!(self.stdout, cursor::MoveTo(left + 35, bottom - 21))?;
-        write!(self.stdout, "JOINING")?;
-        queue!(self.stdout, cursor::MoveTo(left + 35, bottom - 20))?;
-        match game_state.networking.connection_task {
+        match game_state.networking.peer {
             Option::Some(_) => {
-                write!(self.stdout, "Joining...")?;
+                self.back.put_str(left + 35, bottom - 19, "Connected");
             }
             Option::None => {
-                write!(self.stdout, "Joining Broken")?;
+                self.back
+                    .put_str(left + 35, bottom - 19, "Looking for a game...");
             }
         }
+        self.draw_buttons(game_state);
 
-        queue!(self.stdout, cursor::MoveTo(left + 35, bottom - 19))?;
-        match game_state.networking.peer {
-            Option::Some(_) => {
-                write!(self.stdout, "Connected            ")?;
+        self.present(game_state)
+    }
+
+    /// Menu entry drawn at the terminal cell, used to pick entries with the mouse
+    pub fn menu_entry_at(
+        &self,
+        game_state: &GameState,
+        column: u16,
+        row: u16,
+    ) -> Option<MenuEntry> {
+        let (column, _) = self.canvas_cell(column, row);
+        let (left, _, _, bottom) = self.get_game_bounds();
+        match game_state.main_menu.screen {
+            Screen::Main => {
+                let menu_left = main_menu_left(left);
+                if !self.shows_row(row, bottom - 20) || column < menu_left {
+                    return None;
+                }
+                let index = (column - menu_left) / MENU_ENTRY_WIDTH;
+                MenuItem::ALL
+                    .get(index as usize)
+                    .copied()
+                    .map(MenuEntry::Item)
             }
-            Option::None => {
-                write!(self.stdout, "Looking for a game...")?;
+            Screen::Hosting | Screen::Joining if self.shows_row(row, bottom - BUTTON_ROW) => self
+                .buttons(game_state)
+                .into_iter()
+                .find(|&(_, x, label)| (x..x + label.len() as u16).contains(&column))
+                .map(|(entry, _, _)| entry),
+            _ => None,
+        }
+    }
+
+    /// Play field column at the terminal cell, None outside the walls
+    pub fn field_column(&self, column: u16, row: u16) -> Option<u16> {
+        let (column, row) = self.canvas_cell(column, row);
+        let (left, right, top, bottom) = self.get_game_bounds();
+        (column > left && column < right && row > top && row < bottom).then(|| column - left)
+    }
+
+    /// Lists the key bindings, the selected action can be rebound
+    pub fn render_controls_menu(
+        &mut self,
+        game_state: &mut GameState,
+    ) -> Result<(), Box<dyn Error>> {
+        if !self.begin_frame(game_state) {
+            return self.present(game_state);
+        }
+
+        let (left, _, _, bottom) = self.get_game_bounds();
+        let keymap = &game_state.keymap;
+        let menu = &game_state.controls_menu;
+
+        self.back.put_str(left + 35, bottom - 30, "CONTROLS");
+        let mut y = bottom - 28;
+        for action in Action::ALL {
+            let keys: Vec<String> = keymap.keys(action).iter().map(Key::to_string).collect();
+            let line = format!("{:<14}{}", action.label(), keys.join(", "));
+            if action == menu.action() {
+                self.back
+                    .put_styled(left + 35, y, &format!(" > {line}"), SELECTED_STYLE);
+            } else {
+                self.back.put_str(left + 35, y, &format!("   {line}"));
             }
+            y += 1;
         }
 
-        self.stdout.flush()?;
+        y += 1;
+        if menu.capturing {
+            self.back.put_styled(
+                left + 35,
+                y,
+                &format!(
+                    "Press a key to add it to {} or remove it",
+                    menu.action().label()
+                ),
+                PAUSE_STYLE,
+            );
+        } else {
+            let hint = format!(
+                "{}/{} - select | {} - add or remove a key | {} - reset | {} - back",
+                keymap.hint(Action::MoveLeft),
+                keymap.hint(Action::MoveRight),
+                keymap.hint(Action::Shoot),
+                keymap.hint(Action::Pause),
+                keymap.hint(Action::Quit),
+            );
+            self.back.put_styled(left + 35, y, &hint, HINT_STYLE);
+        }
+        if let Some(ref message) = menu.message {
+            self.back.put_styled(left + 35, y + 1, message, ERROR_STYLE);
+        }
 
-        Ok(())
+        self.present(game_state)
     }
 
-    pub fn draw_menu_items(
+    /// Lists the options kept between runs, the selected one can be changed
+    pub fn render_settings_menu(
         &mut self,
-        score: i32,
-        high_score: i32,
-        player_lives: u16,
-        paused: bool,
+        game_state: &mut GameState,
     ) -> Result<(), Box<dyn Error>> {
+        if !self.begin_frame(game_state) {
+            return self.present(game_state);
+        }
+
         let (left, _, _, bottom) = self.get_game_bounds();
-        queue!(self.stdout, cursor::MoveTo(left + 2, bottom - 2))?;
-        write!(self.stdout, "q - exit")?;
+        let keymap = &game_state.keymap;
+        let menu = &game_state.settings_menu;
+
+        self.back.put_str(left + 35, bottom - 30, "SETTINGS");
+        let mut y = bottom - 28;
+        for item in SettingsItem::ALL {
+            let value = match menu.editing {
+                Some(ref text) if item == menu.item() => format!("{text}_"),
+                _ => game_state.settings.value(item),
+            };
+            let line = format!("{:<16}{}", item.label(), value);
+            if item == menu.item() {
+                self.back
+                    .put_styled(left + 35, y, &format!(" > {line}"), SELECTED_STYLE);
+            } else {
+                self.back.put_str(left + 35, y, &format!("   {line}"));
+            }
+            y += 1;
+        }
 
-        queue!(self.stdout, cursor::MoveTo(left + 15, bottom - 2))?;
-        if paused {
-            write!(self.stdout, "p - unpause")?;
+        y += 1;
+        if menu.editing.is_some() {
+            self.back.put_styled(
+                left + 35,
+                y,
+                &format!(
+                    "Type the new {}, enter - save | tab - cancel",
+                    menu.item().label().to_lowercase()
+                ),
+                PAUSE_STYLE,
+            );
         } else {
-            write!(self.stdout, "p - pause")?;
+            let hint = format!(
+                "{}/{} - select | {} - change | {} - back",
+                keymap.hint(Action::MoveLeft),
+                keymap.hint(Action::MoveRight),
+                keymap.hint(Action::Shoot),
+                keymap.hint(Action::Quit),
+            );
+            self.back.put_styled(left + 35, y, &hint, HINT_STYLE);
+            self.back.put_styled(
+                left + 35,
+                y + 1,
+                "Changes apply to the next game",
+                HINT_STYLE,
+            );
+        }
+        if let Some(ref message) = menu.message {
+            self.back.put_styled(left + 35, y + 2, message, ERROR_STYLE);
         }
-        queue!(self.stdout, cursor::MoveTo(left + 28, bottom - 2))?;
-        write!(self.stdout, "r - restart")?;
 
-        queue!(self.stdout, cursor::MoveTo(left + 50, bottom - 2))?;
-        write!(self.stdout, "score - {}", score)?;
+        self.present(game_state)
+    }
 
-        queue!(self.stdout, cursor::MoveTo(left + 70, bottom - 2))?;
-        write!(self.stdout, "HIGHSCORE - {}", high_score)?;
+    /// Clears the back buffer and draws the borders and menu items shared by every screen.
+    ///
+    /// Returns false when the terminal is too small, the back buffer then only holds the warning.
+    fn begin_frame(&mut self, game_state: &GameState) -> bool {
+        let (columns, rows) = self.canvas_size();
+        if self.back.size() != (columns, rows) {
+            self.back = FrameBuffer::new(columns, rows);
+        }
+        self.back.clear();
 
-        queue!(self.stdout, cursor::MoveTo(left + 90, bottom - 2))?;
-        write!(self.stdout, "                     ")?;
+        if self.rows < MIN_ROWS || self.columns < MIN_COLUMNS {
+            self.back.put_str(0, 0, "Terminal too small");
+            return false;
+        }
 
-        queue!(self.stdout, cursor::MoveTo(left + 90, bottom - 2))?;
-        write!(
-            self.stdout,
-            "Lives - {}",
-            "()".repeat(player_lives as usize)
-        )?;
+        self.render_borders();
+        self.draw_menu_items(
+            game_state.score,
+            game_state.high_score,
+            game_state.player_lives,
+            game_state.paused,
+            &game_state.keymap,
+        );
 
-        Ok(())
+        true
     }
 
-    pub fn draw_pause(&mut self) -> Result<(), Box<dyn Error>> {
-        let (left, _, _, bottom) = self.get_game_bounds();
-        queue!(self.stdout, cursor::MoveTo(left + 45, bottom - 20))?;
-        write!(self.stdout, "|  PAUSE (p to unpause)  |")?;
-        self.stdout.flush()?;
+    /// Sends the cells that differ from the last presented frame and flushes the backend.
+    /// A resize or `request_clear_render` clears the terminal and repaints everything.
+    fn present(&mut self, game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+        let (columns, rows) = self.back.size();
+        if columns > self.columns || rows > self.rows {
+            self.back = self.back.shrunk(self.columns, self.rows);
+        } else if (columns, rows) != (self.columns, self.rows) {
+            self.back = self.back.scaled(self.scale(), self.columns, self.rows);
+        }
+        if self.wsize_updated
+            || game_state.request_clear_render
+            || self.front.size() != self.back.size()
+        {
+            game_state.request_clear_render = false;
+            self.wsize_updated = false;
+
+            self.backend.clear()?;
+            self.front = FrameBuffer::new(self.columns, self.rows);
+        }
+
+        // The terminal is left in the default style between frames
+        let mut current_style = Style::default();
+        let mut run = String::new();
+        for y in 0..self.rows {
+            let mut x = 0;
+            while x < self.columns {
+                let Some(cell) = self
+                    .back
+                    .get(x, y)
+                    .filter(|&c| Some(c) != self.front.get(x, y))
+                else {
+                    x += 1;
+                    continue;
+                };
+
+                // Collect the run of changed cells sharing a style so it is sent with a
+                // single cursor move
+                let start = x;
+                let style = cell.style;
+                run.clear();
+                while let Some(next) = self.back.get(x, y) {
+                    if Some(next) == self.front.get(x, y) || next.style != style {
+                        break;
+                    }
+                    run.push(next.ch);
+                    x += 1;
+                }
+
+                let style = if self.color {
+                    style
+                } else {
+                    style.monochrome()
+                };
+                if style != current_style {
+                    self.backend.set_style(style)?;
+                    current_style = style;
+                }
+                self.backend.move_cursor(start, y)?;
+                self.backend.write_str(&run)?;
+            }
+        }
+        if current_style != Style::default() {
+            self.backend.set_style(Style::default())?;
+        }
+
+        std::mem::swap(&mut self.front, &mut self.back);
+        self.backend.flush()?;
 
         Ok(())
     }
 
-    pub fn erase_pause(&mut self) -> Result<(), Box<dyn Error>> {
-        let (left, _, _, bottom) = self.get_game_bounds();
-        queue!(self.stdout, cursor::MoveTo(left + 45, bottom - 20))?;
-        write!(self.stdout, "                          ")?;
-        self.stdout.flush()?;
+    /// Buttons of the Hosting and Joining screens with their column
+    fn buttons(&self, game_state: &GameState) -> Vec<(MenuEntry, u16, &'static str)> {
+        let (left, _, _, _) = self.get_game_bounds();
+        let mut buttons = Vec::new();
+        let mut x = left + 35;
+        if let Screen::Hosting = game_state.main_menu.screen
+            && game_state.networking.connected
+        {
+            buttons.push((MenuEntry::Start, x, "[ Start ]"));
+            x += 12;
+        }
+        buttons.push((MenuEntry::Back, x, "[ Back ]"));
+        buttons
+    }
 
-        Ok(())
+    fn draw_buttons(&mut self, game_state: &GameState) {
+        let (_, _, _, bottom) = self.get_game_bounds();
+        for (_, x, label) in self.buttons(game_state) {
+            self.back.put_str(x, bottom - BUTTON_ROW, label);
+        }
     }
 
-    pub fn draw_game_over(&mut self, score: i32, high_score: i32) -> Result<(), Box<dyn Error>> {
+    fn draw_menu_items(
+        &mut self,
+        score: i32,
+        high_score: i32,
+        player_lives: u16,
+        paused: bool,
+        keymap: &Keymap,
+    ) {
         let (left, _, _, bottom) = self.get_game_bounds();
 
-        if score > high_score {
-            queue!(self.stdout, cursor::MoveTo(left + 30, bottom - 20))?;
-            write!(
-                self.stdout,
-                " GAME OVER | NEW HIGHSCORE: {} | r - restart | q - quit ",
-                score
-            )?;
-        } else {
-            queue!(self.stdout, cursor::MoveTo(left + 35, bottom - 20))?;
-            write!(
-                self.stdout,
-                " GAME OVER | SCORE: {} | r - restart | q - quit ",
-                score
-            )?;
+        let pause = if paused { "unpause" } else { "pause" };
+        let hints = [
+            (2, format!("{} - exit", keymap.hint(Action::Quit))),
+            (15, format!("{} - {pause}", keymap.hint(Action::Pause))),
+            (28, format!("{} - restart", keymap.hint(Action::Restart))),
+        ];
+        // Hints keep their columns unless a long key name pushes them along
+        let mut x = 0;
+        for (column, hint) in hints {
+            x = (left + column).max(x);
+            self.back.put_styled(x, bottom - 2, &hint, HINT_STYLE);
+            x += hint.len() as u16 + 2;
         }
-        self.stdout.flush()?;
 
-        Ok(())
+        self.back.put_styled(
+            left + 50,
+            bottom - 2,
+            &format!("score - {}", score),
+            SCORE_STYLE,
+        );
+
+        self.back.put_styled(
+            left + 70,
+            bottom - 2,
+            &format!("HIGHSCORE - {}", high_score),
+            HIGH_SCORE_STYLE,
+        );
+
+        self.back.put_styled(
+            left + 90,
+            bottom - 2,
+            &format!("Lives - {}", "()".repeat(player_lives as usize)),
+            LIVES_STYLE,
+        );
     }
 
-    pub fn erase_game_over(&mut self) -> Result<(), Box<dyn Error>> {
+    /// Pause menu on a blank box over the middle of the play field
+    fn draw_pause(&mut self, menu: &PauseMenu, keymap: &Keymap) {
         let (left, _, _, bottom) = self.get_game_bounds();
-        queue!(self.stdout, cursor::MoveTo(left + 30, bottom - 20))?;
+        let x = left + 40;
+        let top = bottom - 27;
+        for y in top..top + 13 {
+            self.back.put_str(x - 2, y, &" ".repeat(44));
+        }
+
+        self.back.put_styled(x, top + 1, "PAUSED", PAUSE_STYLE);
+        let mut y = top + 3;
+        for item in PauseItem::ALL {
+            if item != menu.item() {
+                self.back.put_str(x, y, &format!("   {}", item.label()));
+            } else if menu.confirming {
+                let confirm = format!(
+                    " > {}? {} again to confirm",
+                    item.label(),
+                    keymap.hint(Action::Shoot)
+                );
+                self.back.put_styled(x, y, &confirm, GAME_OVER_STYLE);
+            } else {
+                self.back
+                    .put_styled(x, y, &format!(" > {}", item.label()), SELECTED_STYLE);
+            }
+            y += 1;
+        }
 
-        write!(
-            self.stdout,
-            "                                                             "
-        )?;
-        self.stdout.flush()?;
+        let hint = format!(
+            "{}/{} - select | {} - choose | {} - resume",
+            keymap.hint(Action::MoveLeft),
+            keymap.hint(Action::MoveRight),
+            keymap.hint(Action::Shoot),
+            keymap.hint(Action::Pause),
+        );
+        self.back.put_styled(x, y + 1, &hint, HINT_STYLE);
+        if let Some(ref message) = menu.message {
+            self.back.put_styled(x, y + 2, message, PAUSE_STYLE);
+        }
+    }
 
-        Ok(())
+    /// Remaining time of the active power-ups, above the HUD line
+    fn draw_power_ups(&mut self, power_ups: &PowerUps) {
+        let (left, _, _, bottom) = self.get_game_bounds();
+        let timers = [
+            (PowerUpKind::RapidFire, "RAPID", power_ups.rapid_fire),
+            (PowerUpKind::SpreadShot, "SPREAD", power_ups.spread_shot),
+            (PowerUpKind::Piercing, "PIERCE", power_ups.piercing),
+            (PowerUpKind::Shield, "SHIELD", power_ups.shield),
+        ];
+
+        let mut x = left + 2;
+        for (kind, label, seconds) in timers {
+            if seconds <= 0.0 {
+                continue;
+            }
+            let text = format!("{label} {}s", seconds.ceil() as u32);
+            self.back.put_styled(x, bottom - 3, &text, kind.style());
+            x += text.len() as u16 + 3;
+        }
     }
 
-    pub fn terminal_raw_mode(&mut self) -> Result<bool, Box<dyn Error>> {
-        terminal::enable_raw_mode()?;
-        let kb_flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
-            | KeyboardEnhancementFlags::REPORT_EVENT_TYPES;
-        let kb_enhanced = self
-            .stdout
-            .execute(PushKeyboardEnhancementFlags(kb_flags))
-            .is_ok();
-        queue!(self.stdout, cursor::Hide)?;
+    fn draw_wave_banner(&mut self, wave: u32, boss_wave: bool) {
+        let (left, _, _, bottom) = self.get_game_bounds();
+        let banner = if boss_wave {
+            "|  BOSS  |".to_string()
+        } else {
+            format!("|  Wave {wave}  |")
+        };
+        let x = left + (SCREEN_WIDTH - banner.len() as u16) / 2;
+        self.back
+            .put_styled(x, bottom - 20, &banner, WAVE_BANNER_STYLE);
+    }
 
-        Ok(kb_enhanced)
+    /// Health bar across the top row of the play field
+    fn draw_boss_health(&mut self, hp: u16, max_hp: u16) {
+        let (left, _, top, _) = self.get_game_bounds();
+        let filled = (BOSS_HEALTH_BAR_WIDTH * hp as usize).div_ceil(max_hp.max(1) as usize);
+        let (full, empty) = match self.sprite_set {
+            SpriteSet::Ascii => ("#", "-"),
+            SpriteSet::Braille | SpriteSet::Block => ("█", "░"),
+        };
+        let bar = format!(
+            "BOSS [{}{}]",
+            full.repeat(filled),
+            empty.repeat(BOSS_HEALTH_BAR_WIDTH - filled)
+        );
+        let x = left + (SCREEN_WIDTH - bar.chars().count() as u16) / 2;
+        self.back.put_styled(x, top + 1, &bar, BOSS_HEALTH_STYLE);
     }
 
-    pub fn terminal_disable_raw(&mut self, kb_enhanced: bool) -> Result<(), Box<dyn Error>> {
-        if kb_enhanced {
-            let _ = self.stdout.execute(PopKeyboardEnhancementFlags);
+    /// Lists config file problems below the main menu, returns the row after the list
+    fn draw_errors(&mut self, top: u16, title: &str, errors: &[String]) -> u16 {
+        let (left, _, _, _) = self.get_game_bounds();
+        let width = (SCREEN_WIDTH - 8) as usize;
+
+        self.back.put_styled(left + 4, top, title, ERROR_STYLE);
+        let mut y = top + 1;
+        for error in errors.iter().take(MAX_ERROR_LINES) {
+            let line: String = format!("- {error}").chars().take(width).collect();
+            self.back.put_styled(left + 4, y, &line, ERROR_STYLE);
+            y += 1;
         }
-        self.stdout.execute(Clear(ClearType::All))?;
-        self.stdout.execute(cursor::Show)?;
-        terminal::disable_raw_mode()?;
-        self.stdout.flush()?;
-        Ok(())
+        if errors.len() > MAX_ERROR_LINES {
+            let more = format!("  ... and {} more", errors.len() - MAX_ERROR_LINES);
+            self.back.put_styled(left + 4, y, &more, ERROR_STYLE);
+            y += 1;
+        }
+        y
     }
 
-    /// It queues actions without flushing stdout
-    ///
-    /// Remember to flush stdout after calling
-    fn draw_entity(
+    fn draw_game_over(
         &mut self,
-        left: u16,
-        bottom: u16,
-        pos: &Position,
-        prev_pos: &PrevPosition,
-        renderable: &Renderable,
-    ) -> Result<(), Box<dyn Error>> {
-        // Erase previous sprite
-        let erasor = " ".repeat(renderable.width as usize);
-        queue!(
-            self.stdout,
-            cursor::MoveTo(left + prev_pos.x, bottom - prev_pos.y)
-        )?;
-        write!(self.stdout, "{}", erasor)?;
-        queue!(
-            self.stdout,
-            cursor::MoveTo(left + prev_pos.x, bottom - prev_pos.y + 1)
-        )?;
-        write!(self.stdout, "{}", erasor)?;
-
-        // Draw new sprite
-        if !renderable.destroy {
-            queue!(self.stdout, cursor::MoveTo(left + pos.x, bottom - pos.y))?;
-            write!(self.stdout, "{}", renderable.sprite_top)?;
-
-            queue!(
-                self.stdout,
-                cursor::MoveTo(left + pos.x, bottom - pos.y + 1)
-            )?;
-            write!(self.stdout, "{}", renderable.sprite_bottom)?;
+        score: i32,
+        new_high_score: bool,
+        difficulty: Difficulty,
+        keymap: &Keymap,
+    ) {
+        let (left, _, _, bottom) = self.get_game_bounds();
+        let restart = keymap.hint(Action::Restart);
+        let quit = keymap.hint(Action::Quit);
+
+        if new_high_score {
+            self.back.put_styled(
+                left + 30,
+                bottom - 20,
+                &format!(
+                    " GAME OVER | NEW {} HIGHSCORE: {} | {restart} - restart | {quit} - quit ",
+                    difficulty.label().to_uppercase(),
+                    score
+                ),
+                GAME_OVER_STYLE,
+            );
         } else {
-            queue!(self.stdout, cursor::MoveTo(left + pos.x, bottom - pos.y))?;
-            write!(self.stdout, "     ")?;
-
-            queue!(
-                self.stdout,
-                cursor::MoveTo(left + pos.x, bottom - pos.y + 1)
-            )?;
-            write!(self.stdout, "     ")?;
+            self.back.put_styled(
+                left + 35,
+                bottom - 20,
+                &format!(
+                    " GAME OVER | SCORE: {} | {restart} - restart | {quit} - quit ",
+                    score
+                ),
+                GAME_OVER_STYLE,
+            );
         }
+    }
 
-        Ok(())
+    fn draw_entity(&mut self, left: u16, bottom: u16, pos: &Position, renderable: &Renderable) {
+        debug_assert!(
+            [renderable.sprite_top, renderable.sprite_bottom]
+                .iter()
+                .all(|sprite| [0, renderable.width].contains(&sprite.width)),
+            "sprite '{}' doesn't match the entity width {}",
+            renderable.sprite_top.braille,
+            renderable.width
+        );
+        self.back.put_sprite(
+            left + pos.x,
+            bottom - pos.y,
+            renderable.sprite_top.glyphs(self.sprite_set),
+            renderable.style,
+        );
+        self.back.put_sprite(
+            left + pos.x,
+            bottom - pos.y + 1,
+            renderable.sprite_bottom.glyphs(self.sprite_set),
+            renderable.style,
+        );
     }
 
+    /// Walls of the play field in the unscaled frame, `(left, right, top, bottom)`
     fn get_game_bounds(&self) -> (u16, u16, u16, u16) {
-        let center_x = self.wsize.columns / 2;
-        let center_y = self.wsize.rows / 2;
+        let (columns, rows) = self.canvas_size();
+        let center_x = columns / 2;
+        let center_y = rows / 2;
         let half_w = SCREEN_WIDTH / 2;
         let half_h = SCREEN_HEIGHT / 2;
 
@@ -492,36 +1044,35 @@ impl Render {
         (left, right, top, bottom)
     }
 
-    fn render_borders(&mut self) -> Result<(), Box<dyn Error>> {
+    fn render_borders(&mut self) {
         let (left, right, top, bottom) = self.get_game_bounds();
-        let stdout = &mut self.stdout;
 
         let horizontal_wall = "#".repeat(SCREEN_WIDTH as usize);
-        queue!(stdout, Clear(ClearType::All))?;
+
+        let style = Style::default();
 
         // Draw Top Wall
-        queue!(stdout, cursor::MoveTo(left, top))?;
-        write!(stdout, "{}", horizontal_wall)?;
+        self.back.put_sprite(left, top, &horizontal_wall, style);
 
         // Draw Bottom Wall
-        queue!(stdout, cursor::MoveTo(left, bottom))?;
-        write!(stdout, "{}", horizontal_wall)?;
+        self.back.put_sprite(left, bottom, &horizontal_wall, style);
 
-        queue!(stdout, cursor::MoveTo(left, bottom - 4))?;
-        write!(stdout, "{}", horizontal_wall)?;
+        self.back
+            .put_sprite(left, bottom - 4, &horizontal_wall, style);
 
         for i in 0..SCREEN_HEIGHT {
             let y = top + i;
 
             // Left wall
-            queue!(stdout, cursor::MoveTo(left, y))?;
-            write!(stdout, "#")?;
+            self.back.put_sprite(left, y, "#", style);
 
             // Right wall
-            queue!(stdout, cursor::MoveTo(right, y))?;
-            write!(stdout, "#")?;
+            self.back.put_sprite(right, y, "#", style);
         }
-
-        Ok(())
     }
 }
+
+/// Left edge of the main menu entries, centered in the play field
+pub fn main_menu_left(left: u16) -> u16 {
+    left + (SCREEN_WIDTH - MenuItem::ALL.len() as u16 * MENU_ENTRY_WIDTH) / 2
+}
@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{Difficulty, GameState};
+
+/// Progress written by the pause menu. Continuing starts the saved wave over, the
+/// formation and bunkers are not kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SaveGame {
+    pub difficulty: Difficulty,
+    pub wave: u32,
+    /// Saved during the boss fight that follows `wave`
+    pub boss_wave: bool,
+    pub score: i32,
+    pub player_lives: u16,
+    pub local_coop: bool,
+    /// See `GameState::custom_lives`
+    #[serde(default)]
+    pub custom_lives: bool,
+}
+
+impl SaveGame {
+    pub fn from_game(game_state: &GameState) -> Self {
+        SaveGame {
+            difficulty: game_state.difficulty,
+            wave: game_state.wave,
+            boss_wave: game_state.boss_wave,
+            score: game_state.score,
+            player_lives: game_state.player_lives,
+            local_coop: game_state.local_coop,
+            custom_lives: game_state.custom_lives,
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.message().to_string())
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("saved game serializes to TOML")
+    }
+}
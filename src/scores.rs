@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+
+use crate::Difficulty;
+
+/// Best score reached on every difficulty, kept between runs so scores are only
+/// compared with games played on the same difficulty
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HighScores {
+    pub easy: i32,
+    pub normal: i32,
+    pub hard: i32,
+    pub insane: i32,
+}
+
+impl HighScores {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.message().to_string())
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("high scores serialize to TOML")
+    }
+
+    pub fn get(&self, difficulty: Difficulty) -> i32 {
+        match difficulty {
+            Difficulty::Easy => self.easy,
+            Difficulty::Normal => self.normal,
+            Difficulty::Hard => self.hard,
+            Difficulty::Insane => self.insane,
+        }
+    }
+
+    /// Keeps the score if it beats the one on record, returns whether it did
+    pub fn record(&mut self, difficulty: Difficulty, score: i32) -> bool {
+        let best = match difficulty {
+            Difficulty::Easy => &mut self.easy,
+            Difficulty::Normal => &mut self.normal,
+            Difficulty::Hard => &mut self.hard,
+            Difficulty::Insane => &mut self.insane,
+        };
+        if score <= *best {
+            return false;
+        }
+        *best = score;
+        true
+    }
+}
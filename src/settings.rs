@@ -0,0 +1,259 @@
+use serde::{Deserialize, Serialize};
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::Path;
+
+use crate::{DEFAULT_TICK_RATE, SpriteSet};
+
+/// Port the host listens on unless the settings say otherwise
+pub const DEFAULT_PORT: u16 = 23471;
+/// Tick rates offered on the Settings screen, the settings file may use any other
+pub const TICK_RATES: [u32; 4] = [30, 60, 120, 240];
+pub const MAX_STARTING_LIVES: u16 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+    Insane,
+}
+
+/// Parameters a difficulty sets for a whole game
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DifficultyPreset {
+    /// Scales how fast every wave marches
+    pub enemy_speed: f32,
+    /// Scales how often every wave fires
+    pub enemy_fire_rate: f32,
+    /// Scales the waves file's `speed_scale` and `fire_scale`, applied each time the
+    /// last wave repeats
+    pub wave_scaling: f32,
+    /// Lives the Settings screen switches to when the difficulty is picked
+    pub starting_lives: u16,
+    /// Cells per second the cannons move
+    pub player_speed: f32,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 4] = [
+        Difficulty::Easy,
+        Difficulty::Normal,
+        Difficulty::Hard,
+        Difficulty::Insane,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+            Difficulty::Insane => "Insane",
+        }
+    }
+
+    pub fn preset(self) -> DifficultyPreset {
+        match self {
+            Difficulty::Easy => DifficultyPreset {
+                enemy_speed: 0.75,
+                enemy_fire_rate: 0.6,
+                wave_scaling: 0.9,
+                starting_lives: 5,
+                player_speed: 70.0,
+            },
+            Difficulty::Normal => DifficultyPreset {
+                enemy_speed: 1.0,
+                enemy_fire_rate: 1.0,
+                wave_scaling: 1.0,
+                starting_lives: 3,
+                player_speed: 60.0,
+            },
+            Difficulty::Hard => DifficultyPreset {
+                enemy_speed: 1.3,
+                enemy_fire_rate: 1.5,
+                wave_scaling: 1.1,
+                starting_lives: 3,
+                player_speed: 55.0,
+            },
+            Difficulty::Insane => DifficultyPreset {
+                enemy_speed: 1.6,
+                enemy_fire_rate: 2.2,
+                wave_scaling: 1.25,
+                starting_lives: 1,
+                player_speed: 50.0,
+            },
+        }
+    }
+}
+
+/// Options kept between runs. Changes apply to the next game.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub difficulty: Difficulty,
+    pub starting_lives: u16,
+    pub tick_rate: u32,
+    /// Off draws in the terminal's default colors even where colors are supported
+    pub color: bool,
+    pub sprite_set: SpriteSet,
+    /// Port listened on when hosting
+    pub host_port: u16,
+    /// Host connected to when joining
+    pub join_address: SocketAddr,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            difficulty: Difficulty::Normal,
+            starting_lives: 3,
+            tick_rate: DEFAULT_TICK_RATE,
+            color: true,
+            sprite_set: SpriteSet::Braille,
+            host_port: DEFAULT_PORT,
+            join_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), DEFAULT_PORT),
+        }
+    }
+}
+
+impl Settings {
+    /// Reads and validates a settings file, returning every problem found
+    pub fn load(path: &Path) -> Result<Self, Vec<String>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| vec![format!("{}: {}", path.display(), e)])?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Vec<String>> {
+        let settings: Settings = toml::from_str(text).map_err(|e| vec![e.message().to_string()])?;
+
+        let mut errors = Vec::new();
+        if !(1..=MAX_STARTING_LIVES).contains(&settings.starting_lives) {
+            errors.push(format!(
+                "starting_lives must be between 1 and {MAX_STARTING_LIVES}"
+            ));
+        }
+        if settings.tick_rate == 0 {
+            errors.push("tick_rate must be greater than 0".to_string());
+        }
+        if settings.host_port == 0 {
+            errors.push("host_port must be greater than 0".to_string());
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(settings)
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self).expect("settings serialize to TOML")
+    }
+
+    /// Switches the option to its next value, wrapping around
+    pub fn cycle(&mut self, item: SettingsItem) {
+        match item {
+            SettingsItem::Difficulty => {
+                self.difficulty = next(&Difficulty::ALL, self.difficulty);
+                self.starting_lives = self.difficulty.preset().starting_lives;
+            }
+            SettingsItem::StartingLives => {
+                self.starting_lives = self.starting_lives % MAX_STARTING_LIVES + 1;
+            }
+            SettingsItem::TickRate => {
+                self.tick_rate = TICK_RATES
+                    .into_iter()
+                    .find(|&rate| rate > self.tick_rate)
+                    .unwrap_or(TICK_RATES[0]);
+            }
+            SettingsItem::Color => self.color = !self.color,
+            SettingsItem::SpriteSet => self.sprite_set = next(&SpriteSet::ALL, self.sprite_set),
+            SettingsItem::KeyBindings | SettingsItem::HostPort | SettingsItem::JoinAddress => {}
+        }
+    }
+
+    /// Sets a typed in option, the error is shown on the Settings screen
+    pub fn set_text(&mut self, item: SettingsItem, text: &str) -> Result<(), String> {
+        match item {
+            SettingsItem::HostPort => match text.parse() {
+                Ok(port) if port > 0 => self.host_port = port,
+                _ => return Err(format!("'{text}' is not a port")),
+            },
+            SettingsItem::JoinAddress => {
+                self.join_address = text.parse().map_err(|_| {
+                    format!("'{text}' is not an address like 127.0.0.1:{DEFAULT_PORT}")
+                })?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Value shown next to the option on the Settings screen
+    pub fn value(&self, item: SettingsItem) -> String {
+        match item {
+            SettingsItem::Difficulty => self.difficulty.label().to_string(),
+            SettingsItem::StartingLives
+                if self.starting_lives != self.difficulty.preset().starting_lives =>
+            {
+                format!("{} (no high scores)", self.starting_lives)
+            }
+            SettingsItem::StartingLives => self.starting_lives.to_string(),
+            SettingsItem::TickRate => format!("{} Hz", self.tick_rate),
+            SettingsItem::Color => if self.color { "On" } else { "Off" }.to_string(),
+            SettingsItem::SpriteSet => self.sprite_set.label().to_string(),
+            SettingsItem::KeyBindings => String::new(),
+            SettingsItem::HostPort => self.host_port.to_string(),
+            SettingsItem::JoinAddress => self.join_address.to_string(),
+        }
+    }
+}
+
+fn next<T: Copy + PartialEq>(all: &[T], current: T) -> T {
+    let index = all.iter().position(|&value| value == current).unwrap_or(0);
+    all[(index + 1) % all.len()]
+}
+
+/// Rows of the Settings screen, top to bottom
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SettingsItem {
+    Difficulty,
+    StartingLives,
+    TickRate,
+    Color,
+    SpriteSet,
+    /// Opens the Controls screen
+    KeyBindings,
+    HostPort,
+    JoinAddress,
+}
+
+impl SettingsItem {
+    pub const ALL: [SettingsItem; 8] = [
+        SettingsItem::Difficulty,
+        SettingsItem::StartingLives,
+        SettingsItem::TickRate,
+        SettingsItem::Color,
+        SettingsItem::SpriteSet,
+        SettingsItem::KeyBindings,
+        SettingsItem::HostPort,
+        SettingsItem::JoinAddress,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsItem::Difficulty => "Difficulty",
+            SettingsItem::StartingLives => "Starting lives",
+            SettingsItem::TickRate => "Tick rate",
+            SettingsItem::Color => "Color",
+            SettingsItem::SpriteSet => "Sprite set",
+            SettingsItem::KeyBindings => "Key bindings",
+            SettingsItem::HostPort => "Host port",
+            SettingsItem::JoinAddress => "Join address",
+        }
+    }
+
+    /// Options typed in rather than cycled through
+    pub fn is_text(self) -> bool {
+        matches!(self, SettingsItem::HostPort | SettingsItem::JoinAddress)
+    }
+}
@@ -0,0 +1,219 @@
+use crate::{
+    BossPart, BunkerCell, CoPlayer, Direction, Effect, Enemy, EnemyProjectile, GameState, Player,
+    PlayerProjectile, Position, PowerUp, Screen, Ufo, Velocity, WaveSet, create_world_with_waves,
+    process_tick,
+};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use std::error::Error;
+use std::time::Duration;
+
+/// Player input held during a single simulation step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TickInput {
+    pub move_left: bool,
+    pub move_right: bool,
+    pub shoot: bool,
+    /// Column the cannon follows, as when playing with the mouse
+    pub mouse_target: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntityKind {
+    Player,
+    CoPlayer,
+    Enemy,
+    PlayerProjectile,
+    EnemyProjectile,
+    BunkerCell,
+    Ufo,
+    PowerUp,
+    BossPart,
+    /// Explosion or other short animation
+    Effect,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntitySnapshot {
+    pub kind: EntityKind,
+    pub x: u16,
+    pub y: u16,
+}
+
+/// Observable state of the game after a step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    pub tick: u64,
+    pub score: i32,
+    pub high_score: i32,
+    pub player_lives: u16,
+    pub enemy_amount: u16,
+    pub game_over: bool,
+    pub entities: Vec<EntitySnapshot>,
+}
+
+/// Headless game driven one fixed step at a time
+pub struct Simulation {
+    game_state: GameState,
+    step: Duration,
+    tick: u64,
+}
+
+impl Simulation {
+    /// Starts a solo game. The seed makes enemy fire reproducible between runs.
+    pub fn new(tick_rate: u32, seed: u64) -> Self {
+        Self::with_waves(tick_rate, seed, WaveSet::builtin())
+    }
+
+    /// Starts a solo game playing the given waves instead of the built-in ones
+    pub fn with_waves(tick_rate: u32, seed: u64, waves: WaveSet) -> Self {
+        let mut game_state = create_world_with_waves(waves);
+        game_state.main_menu.screen = Screen::Game;
+        game_state.rng = StdRng::seed_from_u64(seed);
+
+        Self {
+            game_state,
+            step: Duration::from_secs_f64(1.0 / tick_rate.max(1) as f64),
+            tick: 0,
+        }
+    }
+
+    /// Applies the input and advances the game by exactly one step.
+    /// Once the game is over the state is frozen and only the snapshot is returned.
+    pub fn step(&mut self, input: TickInput) -> Result<Snapshot, Box<dyn Error>> {
+        if self.game_state.game_over {
+            return Ok(self.snapshot());
+        }
+
+        self.apply_input(input);
+        process_tick(self.step, &mut self.game_state)?;
+        self.tick += 1;
+
+        if self.game_state.game_over_notifier {
+            self.game_state.game_over_notifier = false;
+            self.game_state.end_game();
+        }
+
+        Ok(self.snapshot())
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        let world = &self.game_state.world;
+        let mut entities = Vec::new();
+
+        for (_, pos) in world.query::<&Position>().with::<&Player>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::Player,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+        for (_, pos) in world.query::<&Position>().with::<&CoPlayer>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::CoPlayer,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+        for (_, pos) in world.query::<&Position>().with::<&Enemy>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::Enemy,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+        for (_, pos) in world
+            .query::<&Position>()
+            .with::<&PlayerProjectile>()
+            .iter()
+        {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::PlayerProjectile,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+        for (_, pos) in world.query::<&Position>().with::<&EnemyProjectile>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::EnemyProjectile,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+        for (_, pos) in world.query::<&Position>().with::<&Ufo>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::Ufo,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+        for (_, pos) in world.query::<&Position>().with::<&BossPart>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::BossPart,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+        for (_, pos) in world.query::<&Position>().with::<&PowerUp>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::PowerUp,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+
+        for (_, pos) in world.query::<&Position>().with::<&Effect>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::Effect,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+
+        for (_, pos) in world.query::<&Position>().with::<&BunkerCell>().iter() {
+            entities.push(EntitySnapshot {
+                kind: EntityKind::BunkerCell,
+                x: pos.x,
+                y: pos.y,
+            });
+        }
+
+        Snapshot {
+            tick: self.tick,
+            score: self.game_state.score,
+            high_score: self.game_state.high_score,
+            player_lives: self.game_state.player_lives,
+            enemy_amount: self.game_state.enemy_amount,
+            game_over: self.game_state.game_over,
+            entities,
+        }
+    }
+
+    pub fn game_state(&self) -> &GameState {
+        &self.game_state
+    }
+
+    pub fn game_state_mut(&mut self) -> &mut GameState {
+        &mut self.game_state
+    }
+
+    fn apply_input(&mut self, input: TickInput) {
+        let handler = &mut self.game_state.player_input_handler;
+        handler.move_player_left = input.move_left;
+        handler.move_player_right = input.move_right;
+        handler.player_shoot = input.shoot;
+        handler.mouse_target = input.mouse_target;
+
+        if let Ok(vel) = self
+            .game_state
+            .world
+            .query_one_mut::<&mut Velocity>(self.game_state.player_entity)
+        {
+            vel.direction = match (input.move_left, input.move_right) {
+                (true, false) => Direction::Left,
+                (false, true) => Direction::Right,
+                _ => Direction::None,
+            };
+        }
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+/// Glyphs the sprites are drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SpriteSet {
+    Braille,
+    /// Plain ASCII for fonts and serial or SSH sessions without braille
+    Ascii,
+    /// Block elements, between the other two in how widely fonts carry them
+    Block,
+}
+
+impl SpriteSet {
+    pub const ALL: [SpriteSet; 3] = [SpriteSet::Braille, SpriteSet::Ascii, SpriteSet::Block];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SpriteSet::Braille => "Braille",
+            SpriteSet::Ascii => "ASCII",
+            SpriteSet::Block => "Block",
+        }
+    }
+
+    /// Reads the name used by `--sprites` and the settings file
+    pub fn from_name(name: &str) -> Option<SpriteSet> {
+        SpriteSet::ALL
+            .into_iter()
+            .find(|set| set.label().eq_ignore_ascii_case(name))
+    }
+}
+
+/// One row of a sprite in every sprite set. The sets must be equally wide, so the
+/// set drawn never changes which cells collide; `new` panics otherwise, at compile
+/// time for sprites declared as constants.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Sprite {
+    pub braille: &'static str,
+    pub ascii: &'static str,
+    pub block: &'static str,
+    /// Cells covered in each set
+    pub width: u16,
+}
+
+impl Sprite {
+    pub const EMPTY: Sprite = Sprite::text("");
+
+    pub const fn new(braille: &'static str, ascii: &'static str, block: &'static str) -> Sprite {
+        let width = cell_count(braille);
+        assert!(
+            cell_count(ascii) == width && cell_count(block) == width,
+            "every sprite set must be as wide as the braille sprite"
+        );
+        Sprite {
+            braille,
+            ascii,
+            block,
+            width,
+        }
+    }
+
+    /// Drawn the same in every set
+    pub const fn text(text: &'static str) -> Sprite {
+        Sprite::new(text, text, text)
+    }
+
+    pub const fn glyphs(self, set: SpriteSet) -> &'static str {
+        match set {
+            SpriteSet::Braille => self.braille,
+            SpriteSet::Ascii => self.ascii,
+            SpriteSet::Block => self.block,
+        }
+    }
+}
+
+/// Counts characters, every glyph the sprite sets use covers a single cell
+const fn cell_count(text: &str) -> u16 {
+    let bytes = text.as_bytes();
+    let mut count = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        // Continuation bytes of a multi-byte character don't start a new one
+        if bytes[i] & 0xC0 != 0x80 {
+            count += 1;
+        }
+        i += 1;
+    }
+    count
+}
@@ -1,40 +1,98 @@
-use crate::{Direction, NetPacket};
+use crate::{
+    Action, CollisionEvent, Difficulty, Direction, HighScores, Keymap, NetPacket, SaveGame,
+    Settings, SettingsItem, WaveSet,
+};
 use hecs::{Entity, World};
-use tokio::sync::mpsc::UnboundedSender;
+use rand::rngs::StdRng;
+use std::time::{Duration, Instant};
 
 pub struct GameState {
     pub world: World,
 
     pub player_lives: u16,
+    /// Seconds left of the red flash shown after the player is hit
+    pub player_hit_timer: f32,
     pub player_entity: Entity,
     pub player_projectile_exists: bool,
     pub enemy_direction: Direction,
-    pub score_updated: bool,
     pub score: i32,
+    /// Best score on the difficulty being played
     pub high_score: i32,
+    pub new_high_score: bool,
+    /// Best scores of every difficulty, kept between runs
+    pub high_scores: HighScores,
+    /// Difficulty the current game was started with, settings changed while it runs
+    /// only apply to the next one
+    pub difficulty: Difficulty,
+    /// Started with other lives than the difficulty's preset, such games don't set
+    /// high scores
+    pub custom_lives: bool,
 
+    /// Speed and fire rate factors of the current wave and difficulty, the
+    /// formation is spawned with them
     pub enemy_speed_multiplier: f32,
     pub enemy_proj_prob_multiplier: f32,
     pub enemy_amount: u16,
+    pub waves: WaveSet,
+    /// 1-based number of the current wave
+    pub wave: u32,
+    /// Invaders the current wave started with, for the speed-up as it thins out
+    pub formation_size: u16,
+    /// Sprite frame the whole formation is showing
+    pub formation_frame: usize,
+    /// Cells marched since the formation last switched frames
+    pub formation_cells: u16,
+    /// Invaders allowed to dive at once in the current wave
+    pub max_divers: u8,
+    /// Seconds until the next invader breaks formation
+    pub dive_timer: f32,
+    /// The current stage is the boss fight that follows `wave`
+    pub boss_wave: bool,
+    /// Seconds left of the "Wave N" banner, the next wave spawns once it is gone
+    pub wave_banner_timer: f32,
+    /// Problems found in the waves file, shown on the main menu
+    pub wave_errors: Vec<String>,
+    pub keymap: Keymap,
+    /// Problems found in the keymap file, shown on the main menu
+    pub keymap_errors: Vec<String>,
+    /// Options the game was started with, edits apply to the next game
+    pub settings: Settings,
+    /// Problems found in the settings file, shown on the main menu
+    pub settings_errors: Vec<String>,
+    /// Seconds until the next mystery UFO crosses the screen
+    pub ufo_timer: f32,
+    /// Collisions found by the collision pass of the current tick
+    pub collision_events: Vec<CollisionEvent>,
 
     pub game_over: bool,
     pub game_over_notifier: bool,
     pub paused: bool,
     pub pause_notifier: bool,
+    pub pause_menu: PauseMenu,
+    /// Saved game the next restart continues instead of starting over
+    pub resume: Option<SaveGame>,
 
     pub restart_notifier: bool,
 
     pub player_input_handler: PlayerInputHandler,
     pub coplayer_handler: CoPlayerHandler,
+    /// Both cannons are played on this keyboard, without networking
+    pub local_coop: bool,
     pub main_menu: MainMenu,
+    pub controls_menu: ControlsMenu,
+    pub settings_menu: SettingsMenu,
     pub networking: GameNetworking,
     pub request_clear_render: bool,
+
+    pub rng: StdRng,
 }
 
 pub struct PlayerInputHandler {
     pub player_shoot: bool,
     pub move_player_left: bool,
     pub move_player_right: bool,
+    /// Field column the cannon steers toward while following the mouse
+    pub mouse_target: Option<u16>,
 }
 
 pub struct CoPlayerHandler {
@@ -42,15 +100,65 @@ pub struct CoPlayerHandler {
     pub x: u16,
     pub player_shoot: bool,
     pub projectile_exists: bool,
+    /// Keys held by the co-player in local co-op
+    pub move_left: bool,
+    pub move_right: bool,
+    pub shoot_held: bool,
 
     pub host_entities: Option<Vec<(u16, u16, u16)>>,
-    pub old_host_entities: Option<Vec<(u16, u16, u16)>>,
+    pub host_bunkers: Vec<(u16, u16, u8)>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MenuItem {
     HostGame,
     JoinGame,
     PlaySolo,
+    LocalCoop,
+    Continue,
+    Controls,
+    Settings,
+}
+
+impl MenuItem {
+    /// Entries of the main menu, left to right
+    pub const ALL: [MenuItem; 7] = [
+        MenuItem::HostGame,
+        MenuItem::JoinGame,
+        MenuItem::PlaySolo,
+        MenuItem::LocalCoop,
+        MenuItem::Continue,
+        MenuItem::Controls,
+        MenuItem::Settings,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MenuItem::HostGame => "HostGame",
+            MenuItem::JoinGame => "JoinGame",
+            MenuItem::PlaySolo => "PlaySolo",
+            MenuItem::LocalCoop => "LocalCoop",
+            MenuItem::Continue => "Continue",
+            MenuItem::Controls => "Controls",
+            MenuItem::Settings => "Settings",
+        }
+    }
+
+    pub fn next(self) -> MenuItem {
+        let index = MenuItem::ALL
+            .iter()
+            .position(|&item| item == self)
+            .unwrap_or(0);
+        MenuItem::ALL[(index + 1) % MenuItem::ALL.len()]
+    }
+
+    pub fn previous(self) -> MenuItem {
+        let index = MenuItem::ALL
+            .iter()
+            .position(|&item| item == self)
+            .unwrap_or(0);
+        MenuItem::ALL[(index + MenuItem::ALL.len() - 1) % MenuItem::ALL.len()]
+    }
 }
 
 pub enum Screen {
@@ -58,30 +166,165 @@ pub enum Screen {
     Hosting,
     Joining,
     Game,
+    Controls,
+    Settings,
 }
 
 pub struct MainMenu {
     pub active_menu_item: MenuItem,
     pub screen: Screen,
+    /// Shown under the entries, e.g. when there is no saved game to continue
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PauseItem {
+    Resume,
+    Restart,
+    Settings,
+    Save,
+    QuitToMenu,
+}
+
+impl PauseItem {
+    /// Entries of the pause menu, top to bottom
+    pub const ALL: [PauseItem; 5] = [
+        PauseItem::Resume,
+        PauseItem::Restart,
+        PauseItem::Settings,
+        PauseItem::Save,
+        PauseItem::QuitToMenu,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PauseItem::Resume => "Resume",
+            PauseItem::Restart => "Restart",
+            PauseItem::Settings => "Settings",
+            PauseItem::Save => "Save",
+            PauseItem::QuitToMenu => "Quit to menu",
+        }
+    }
+
+    /// Entries that throw the current game away and ask first
+    pub fn needs_confirmation(self) -> bool {
+        matches!(self, PauseItem::Restart | PauseItem::QuitToMenu)
+    }
+}
+
+/// Cursor of the menu shown while the game is paused
+#[derive(Default)]
+pub struct PauseMenu {
+    pub selected: usize,
+    /// The selected entry was chosen once and waits to be chosen again
+    pub confirming: bool,
+    /// Result of the last choice, e.g. a saved game
+    pub message: Option<String>,
+}
+
+impl PauseMenu {
+    pub fn item(&self) -> PauseItem {
+        PauseItem::ALL[self.selected % PauseItem::ALL.len()]
+    }
+
+    /// Moves the cursor by `step` entries, dropping a pending confirmation
+    pub fn select(&mut self, step: isize) {
+        let count = PauseItem::ALL.len() as isize;
+        self.selected = (self.selected as isize + step).rem_euclid(count) as usize;
+        self.confirming = false;
+        self.message = None;
+    }
+}
+
+/// Cursor of the Controls screen
+#[derive(Default)]
+pub struct ControlsMenu {
+    pub selected: usize,
+    /// The next key pressed is bound to or unbound from the selected action
+    pub capturing: bool,
+    /// Result of the last change, e.g. a rejected conflicting key
+    pub message: Option<String>,
+}
+
+impl ControlsMenu {
+    pub fn action(&self) -> Action {
+        Action::ALL[self.selected % Action::ALL.len()]
+    }
+}
+
+/// Cursor of the Settings screen
+#[derive(Default)]
+pub struct SettingsMenu {
+    pub selected: usize,
+    /// Opened from the pause menu, leaving goes back to the paused game
+    pub in_game: bool,
+    /// Text typed so far while a text option is being edited
+    pub editing: Option<String>,
+    /// Result of the last change, e.g. a rejected address or a failed save
+    pub message: Option<String>,
+}
+
+impl SettingsMenu {
+    pub fn item(&self) -> SettingsItem {
+        SettingsItem::ALL[self.selected % SettingsItem::ALL.len()]
+    }
 }
 
 pub struct GameNetworking {
     pub stay_online: bool,
-    /// NEVER SET IT TO Option::None
-    pub connection_task: Option<tokio::task::JoinHandle<()>>,
+    /// Set by the frontend while its connection task (listening or joining) is alive
+    pub listening: bool,
     pub host: bool,
     pub peer: Option<std::net::SocketAddr>,
+    pub connected: bool,
 
-    pub tx_writer: Option<UnboundedSender<NetPacket>>,
+    /// Packets produced by the simulation, drained and sent by the frontend
+    pub outbox: Vec<NetPacket>,
 }
 
 impl GameState {
+    /// Freezes the game and records the high score, unless the game started with
+    /// custom lives
+    pub fn end_game(&mut self) {
+        self.game_over = true;
+        if self.custom_lives {
+            return;
+        }
+        self.new_high_score = self.score > self.high_score;
+        if self.new_high_score {
+            self.high_score = self.score;
+        }
+        self.high_scores.record(self.difficulty, self.score);
+    }
+
     pub fn exit_to_menu(&mut self) {
         self.main_menu.screen = Screen::Main;
         self.request_clear_render = true;
         self.restart_notifier = true;
+        self.local_coop = false;
+        self.settings_menu = SettingsMenu::default();
         self.networking.reset();
     }
+
+    /// Fires the local cannon. A player who joined an online game plays the host's
+    /// co-player, whose shot is sent along; in local co-op the co-player only fires
+    /// with its own key.
+    pub fn press_shoot(&mut self) {
+        self.player_input_handler.player_shoot = true;
+        if self.networking.connected && !self.networking.host {
+            self.coplayer_handler.player_shoot = true;
+        }
+    }
+
+    /// Pauses or resumes the game, telling the other player in online co-op so both
+    /// games stop together
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.pause_menu = PauseMenu::default();
+        if self.networking.connected {
+            self.networking.outbox.push(NetPacket::Pause { paused });
+        }
+    }
 }
 
 impl GameNetworking {
@@ -95,9 +338,54 @@ impl GameNetworking {
         self.host = false;
     }
 
+    pub fn connect(&mut self, peer: std::net::SocketAddr) {
+        self.peer = Some(peer);
+        self.connected = true;
+    }
+
     pub fn reset(&mut self) {
         self.stay_online = false;
         self.host = false;
         self.peer = Option::None;
+        self.connected = false;
+        self.outbox.clear();
+    }
+}
+
+pub const DEFAULT_TICK_RATE: u32 = 60;
+/// Longest stretch of time simulated for a single frame, so a long stall
+/// (e.g. a suspended terminal) does not trigger a burst of catch-up ticks
+const MAX_FRAME_TIME: Duration = Duration::from_millis(100);
+
+/// Accumulator driving the simulation at a fixed rate, independent of how
+/// often frames are rendered
+pub struct FixedTimestep {
+    pub step: Duration,
+    accumulator: Duration,
+    last_update: Instant,
+}
+
+impl FixedTimestep {
+    pub fn new(tick_rate: u32) -> Self {
+        Self {
+            step: Duration::from_secs_f64(1.0 / tick_rate.max(1) as f64),
+            accumulator: Duration::ZERO,
+            last_update: Instant::now(),
+        }
+    }
+
+    /// Adds the time elapsed since the last call and returns how many whole
+    /// steps should be simulated. The fractional remainder is kept for the next frame.
+    pub fn advance(&mut self, now: Instant) -> u32 {
+        // Time beyond a stall's worth is dropped instead of fast-forwarded through
+        self.accumulator += now.duration_since(self.last_update).min(MAX_FRAME_TIME);
+        self.last_update = now;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            self.accumulator -= self.step;
+            steps += 1;
+        }
+        steps
     }
 }
@@ -1,486 +1,1325 @@
 use crate::state::CoPlayerHandler;
 use crate::{
-    CoPlayer, CoPlayerProjectile, Direction, Enemy, EnemyProjectile, GameNetworking, GameState,
-    MainMenu, MenuItem, NetPacket, Player, PlayerInputHandler, PlayerProjectile, Position,
-    PrevPosition, ProjectileSpawner, Render, Renderable, Screen, Velocity,
+    Boss, BossPart, BossPhase, BoundingBox, BunkerCell, CoPlayer, CoPlayerProjectile, Collides,
+    CollisionEvent, Color, ControlsMenu, Despawn, Direction, Diving, Drift, Effect, EffectKind,
+    Enemy, EnemyKind, EnemyProjectile, FirePattern, FloatingText, GameNetworking, GameState,
+    Health, HighScores, Keymap, LAYER_BOSS, LAYER_BUNKER, LAYER_ENEMY, LAYER_ENEMY_SHOT,
+    LAYER_PLAYER, LAYER_PLAYER_SHOT, LAYER_POWER_UP, LAYER_UFO, MainMenu, MenuItem,
+    NET_BOSS_HEALTH, NET_BOSS_PART, NET_COPLAYER, NET_COPLAYER_PROJECTILE,
+    NET_EFFECT_ENEMY_EXPLOSION, NET_EFFECT_PLAYER_EXPLOSION, NET_EFFECT_SHOT_BURST, NET_ENEMY,
+    NET_ENEMY_CRAB, NET_ENEMY_PROJECTILE, NET_ENEMY_SQUID, NET_FORMATION_FRAME, NET_PLAYER,
+    NET_PLAYER_PROJECTILE, NET_POWER_UP_EXTRA_LIFE, NET_POWER_UP_PIERCING, NET_POWER_UP_RAPID_FIRE,
+    NET_POWER_UP_SHIELD, NET_POWER_UP_SPREAD_SHOT, NET_UFO, NetPacket, PauseMenu, Pierced, Player,
+    PlayerInputHandler, PlayerProjectile, Position, PowerUp, PowerUpKind, PowerUps,
+    ProjectileSpawner, Renderable, SaveGame, Screen, Settings, SettingsMenu, Sprite, Style, Ufo,
+    Velocity, WaveEvent, WaveSet,
 };
-use crossterm::terminal;
 use hecs::Entity;
 use hecs::World;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Ordering;
 use std::error::Error;
-use std::io::stdout;
 use std::time::Duration;
 
 pub const SCREEN_WIDTH: u16 = 120;
 pub const SCREEN_HEIGHT: u16 = 40;
 
-pub fn create_world() -> Result<(GameState, Render), Box<dyn Error>> {
+pub const PLAYER_STYLE: Style = Style::fg(Color::Green);
+pub const COPLAYER_STYLE: Style = Style::fg(Color::Cyan);
+pub const PLAYER_HIT_STYLE: Style = Style::fg(Color::Red).bold();
+pub const ENEMY_PROJECTILE_STYLE: Style = Style::fg(Color::Red);
+/// Cannon of both players, `(sprite_top, sprite_bottom)`
+pub const PLAYER_SPRITE: (Sprite, Sprite) = (
+    Sprite::new("⣆⡜⣛⢣⣠", " _A_ ", "▗▟█▙▖"),
+    Sprite::new("⣿⣿⣿⣿⣿", "[###]", "█████"),
+);
+/// Drawn on the top row of the shot, right above the cannon
+pub const PLAYER_SHOT_SPRITE: Sprite = Sprite::new("⣿", "|", "█");
+/// Drawn on the bottom row of the shot
+pub const ENEMY_SHOT_SPRITE: Sprite = Sprite::new("⣿", "!", "▓");
+/// Enemy colors from the top row of the formation down
+pub const ENEMY_ROW_STYLES: [Style; 3] = [
+    EnemyKind::Squid.stats().style,
+    EnemyKind::Crab.stats().style,
+    EnemyKind::Octopus.stats().style,
+];
+pub const BUNKER_STYLE: Style = Style::fg(Color::Green);
+pub const UFO_STYLE: Style = Style::fg(Color::Red).bold();
+const UFO_SCORE_STYLE: Style = Style::fg(Color::Yellow).bold();
+/// How long the player stays red after being hit
+const PLAYER_HIT_FLASH: f32 = 0.3;
+/// Columns kept clear between the side walls and anything moving along them
+const FIELD_MARGIN: u16 = 2;
+/// Highest row anything reaches, right below the top wall
+const FIELD_TOP: u16 = SCREEN_HEIGHT - 1;
+/// Columns the cannon can move between, the formation and divers keep to them too
+const PLAYER_MIN_X: u16 = FIELD_MARGIN;
+const PLAYER_MAX_X: u16 = SCREEN_WIDTH - FIELD_MARGIN - PLAYER_BOUNDS.width;
+
+/// Bunker outline from its top row down, every `#` is a separate cell
+const BUNKER_SHAPE: [&str; 3] = [" ###### ", "########", "##    ##"];
+const BUNKER_TOP: u16 = 13;
+/// Left edge of every bunker
+const BUNKER_X: [u16; 4] = [14, 42, 70, 98];
+const BUNKER_CELL_HP: u8 = 3;
+
+pub const PLAYER_BOUNDS: BoundingBox = BoundingBox {
+    width: 5,
+    height: 2,
+    y_offset: 0,
+    layer: LAYER_PLAYER,
+    mask: LAYER_ENEMY_SHOT | LAYER_ENEMY,
+};
+pub const PLAYER_SHOT_BOUNDS: BoundingBox = BoundingBox {
+    width: 1,
+    height: 1,
+    y_offset: 0,
+    layer: LAYER_PLAYER_SHOT,
+    mask: LAYER_ENEMY | LAYER_ENEMY_SHOT | LAYER_UFO | LAYER_BUNKER | LAYER_BOSS,
+};
+pub const ENEMY_BOUNDS: BoundingBox = BoundingBox {
+    width: 5,
+    height: 2,
+    y_offset: 0,
+    layer: LAYER_ENEMY,
+    mask: LAYER_PLAYER_SHOT | LAYER_PLAYER,
+};
+/// Enemy projectiles are drawn one row below their position
+pub const ENEMY_SHOT_BOUNDS: BoundingBox = BoundingBox {
+    width: 1,
+    height: 1,
+    y_offset: 1,
+    layer: LAYER_ENEMY_SHOT,
+    mask: LAYER_PLAYER | LAYER_PLAYER_SHOT | LAYER_BUNKER,
+};
+const BOSS_PART_BOUNDS: BoundingBox = BoundingBox {
+    width: 5,
+    height: 2,
+    y_offset: 0,
+    layer: LAYER_BOSS,
+    mask: LAYER_PLAYER_SHOT,
+};
+/// Bunker cells erode under shots of either side and invaders marching through them
+pub const BUNKER_CELL_BOUNDS: BoundingBox = BoundingBox {
+    width: 1,
+    height: 1,
+    y_offset: 0,
+    layer: LAYER_BUNKER,
+    mask: LAYER_PLAYER_SHOT | LAYER_ENEMY_SHOT | LAYER_ENEMY,
+};
+
+/// Boss part grid, `(offset_x, offset_y, sprite_top, sprite_bottom, weak_point)`.
+/// Shots come from below, so only the core in the lower middle is exposed.
+pub const BOSS_PARTS: [(u16, u16, Sprite, Sprite, bool); 6] = [
+    (
+        0,
+        0,
+        Sprite::new("⢀⣴⣾⣿⣿", " /###", "▗▟███"),
+        Sprite::new("⣿⣿⣿⣿⣿", "#####", "█████"),
+        false,
+    ),
+    (
+        5,
+        0,
+        Sprite::new("⣿⣿⣿⣿⣿", "#####", "█████"),
+        Sprite::new("⣿⣿⣿⣿⣿", "#####", "█████"),
+        false,
+    ),
+    (
+        10,
+        0,
+        Sprite::new("⣿⣿⣷⣦⡀", "###\\ ", "███▙▖"),
+        Sprite::new("⣿⣿⣿⣿⣿", "#####", "█████"),
+        false,
+    ),
+    (
+        0,
+        2,
+        Sprite::new("⣿⡿⠛⢿⣿", "#/ \\#", "█▛▀▜█"),
+        Sprite::new("⠙⠁ ⠈⠻", "'   '", "▀▘ ▝▀"),
+        false,
+    ),
+    (
+        5,
+        2,
+        Sprite::new("⣿⠟⣉⠻⣿", "#/=\\#", "█▀▄▀█"),
+        Sprite::new("⠁⠸⣿⠇⠈", "' @ '", "▘▐█▌▝"),
+        true,
+    ),
+    (
+        10,
+        2,
+        Sprite::new("⣿⡿⠛⢿⣿", "#/ \\#", "█▛▀▜█"),
+        Sprite::new("⠟⠁ ⠈⠋", "'   '", "▀▘ ▝▀"),
+        false,
+    ),
+];
+const BOSS_WIDTH: u16 = 15;
+const BOSS_TOP: u16 = 38;
+const BOSS_POINTS: i32 = 500;
+pub const BOSS_STYLE: Style = Style::fg(Color::Magenta).bold();
+pub const BOSS_WEAK_POINT_STYLE: Style = Style::fg(Color::Red).bold();
+/// The boss cycles through these phases, each lasting the given seconds
+const BOSS_SCRIPT: [(BossPhase, f32); 4] = [
+    (BossPhase::Strafe, 6.0),
+    (BossPhase::Burst, 2.5),
+    (BossPhase::Strafe, 4.0),
+    (BossPhase::Dive, 3.0),
+];
+/// Radians per second of the wall to wall sweep
+const BOSS_STRAFE_FREQUENCY: f32 = 0.6;
+/// Rows the boss swoops down during a dive
+const BOSS_DIVE_DEPTH: f32 = 12.0;
+const BOSS_STRAFE_FIRE_INTERVAL: f32 = 1.2;
+const BOSS_BURST_FIRE_INTERVAL: f32 = 0.5;
+/// Sideways slopes of the projectiles in a burst
+const BOSS_BURST_SLOPES: [f32; 5] = [-0.6, -0.3, 0.0, 0.3, 0.6];
+const BOSS_PROJECTILE_SPEED: f32 = -25.0;
+
+const DIVE_MIN_INTERVAL: f32 = 3.0;
+const DIVE_MAX_INTERVAL: f32 = 6.0;
+/// Chance of a diver leaving through the bottom instead of turning back
+const DIVE_LOOP_CHANCE: f64 = 0.4;
+/// Seconds from leaving the slot to the lowest point of the dive
+const DIVE_DOWN_TIME: f32 = 2.5;
+/// Seconds to get back into the slot, from the lowest point or the top of the field
+const DIVE_BACK_TIME: f32 = 2.0;
+/// Seconds to fall out of the field when looping around
+const DIVE_EXIT_TIME: f32 = 0.2;
+/// Lowest row a returning diver reaches, just above the bunkers
+const DIVE_BOTTOM: f32 = 16.0;
+const DIVE_FIRE_INTERVAL: f32 = 0.7;
+
+/// How long the "Wave N" banner is shown before a wave
+const WAVE_BANNER_TIME: f32 = 2.0;
+/// Horizontal cells an aimed shot may travel per cell it falls
+const AIMED_SHOT_MAX_SLOPE: f32 = 1.0;
+
+pub struct EnemyStats {
+    /// `(sprite_top, sprite_bottom)` of each frame, alternating as the formation marches
+    pub frames: [(Sprite, Sprite); 2],
+    pub style: Style,
+    pub points: i32,
+    pub hp: u8,
+    pub fire_pattern: FirePattern,
+    /// Chance in percent to fire on each step, before the wave multiplier
+    pub fire_probability: f64,
+}
+
+impl EnemyKind {
+    pub const fn stats(self) -> EnemyStats {
+        match self {
+            EnemyKind::Squid => EnemyStats {
+                frames: [
+                    (
+                        Sprite::new("⢀⡴⣿⢦⡀", " /^\\ ", "▗▟█▙▖"),
+                        Sprite::new("⠋⡽⠛⢯⠙", "/'-'\\", "▘▚▀▞▝"),
+                    ),
+                    (
+                        Sprite::new("⢀⡴⣿⢦⡀", " /^\\ ", "▗▟█▙▖"),
+                        Sprite::new("⠘⢯⠛⡽⠃", "\\'-'/", "▝▞▀▚▘"),
+                    ),
+                ],
+                style: Style::fg(Color::Magenta),
+                points: 30,
+                hp: 1,
+                fire_pattern: FirePattern::Aimed,
+                fire_probability: 0.05,
+            },
+            EnemyKind::Crab => EnemyStats {
+                frames: [
+                    (
+                        Sprite::new("⣴⣿⣿⣿⣦", "/OOO\\", "▟███▙"),
+                        Sprite::new("⠏⢹⠛⡏⠹", "/| |\\", "▛▐▀▌▜"),
+                    ),
+                    (
+                        Sprite::new("⣴⣿⣿⣿⣦", "/OOO\\", "▟███▙"),
+                        Sprite::new("⠹⡏⠛⢹⠏", "\\| |/", "▜▌▀▐▛"),
+                    ),
+                ],
+                style: Style::fg(Color::Blue),
+                points: 20,
+                hp: 2,
+                fire_pattern: FirePattern::Straight,
+                fire_probability: 0.1,
+            },
+            EnemyKind::Octopus => EnemyStats {
+                frames: [
+                    (
+                        Sprite::new("⢳⡴⠶⢦⡞", "\\ooo/", "▚▄▄▄▞"),
+                        Sprite::new("⠞⠫⡪⠋⠱", "/^ ^\\", "▞▘▀▝▚"),
+                    ),
+                    (
+                        Sprite::new("⣠⡴⠶⢦⣄", "_ooo_", "▗▄▄▄▖"),
+                        Sprite::new("⠳⠫⡪⠋⠞", "\\^ ^/", "▚▘▀▝▞"),
+                    ),
+                ],
+                style: Style::fg(Color::Yellow),
+                points: 10,
+                hp: 1,
+                fire_pattern: FirePattern::Straight,
+                fire_probability: 0.1,
+            },
+        }
+    }
+
+    pub const fn net_code(self) -> u16 {
+        match self {
+            EnemyKind::Squid => NET_ENEMY_SQUID,
+            EnemyKind::Crab => NET_ENEMY_CRAB,
+            EnemyKind::Octopus => NET_ENEMY,
+        }
+    }
+
+    pub const fn from_net_code(code: u16) -> Option<EnemyKind> {
+        match code {
+            NET_ENEMY_SQUID => Some(EnemyKind::Squid),
+            NET_ENEMY_CRAB => Some(EnemyKind::Crab),
+            NET_ENEMY => Some(EnemyKind::Octopus),
+            _ => None,
+        }
+    }
+}
+
+/// The game is lost once the lowest row of the formation steps down to this row
+const INVASION_ROW: u16 = 10;
+/// Cells the formation marches before switching to its other frame
+const FORMATION_FRAME_CELLS: u16 = 3;
+
+const ENEMY_EXPLOSION_FRAMES: [(Sprite, Sprite); 3] = [
+    (
+        Sprite::new("⢄⠀⡇⠀⡠", "\\ | /", "▚ ▌ ▞"),
+        Sprite::new("⠊⠀⡇⠀⠑", "/ | \\", "▞ ▌ ▚"),
+    ),
+    (
+        Sprite::new("⠐⢌⠀⡡⠂", " \\ / ", " ▚ ▞ "),
+        Sprite::new("⠐⡡⠀⢌⠂", " / \\ ", " ▞ ▚ "),
+    ),
+    (
+        Sprite::new("⠁⠀⠄⠀⠈", "'   '", "▘   ▝"),
+        Sprite::new("⠠⠀⠁⠀⠄", ".   .", "▖   ▗"),
+    ),
+];
+const PLAYER_EXPLOSION_FRAMES: [(Sprite, Sprite); 3] = [
+    (
+        Sprite::new("⠠⡑⣌⢊⠄", " \\|/ ", " ▚▄▞ "),
+        Sprite::new("⣼⣿⣿⣿⣧", "/###\\", "▟███▙"),
+    ),
+    (
+        Sprite::new("⡐⠌⠡⠡⢂", "/ ' \\", "▞ ▘ ▚"),
+        Sprite::new("⣠⢶⣿⡶⣄", "_*#*_", "▗▚█▞▖"),
+    ),
+    (
+        Sprite::new("⠂⠐⠀⠂⠐", "' . '", "▘ ▝ ▘"),
+        Sprite::new("⠠⢀⣀⡀⠄", " ._. ", " ▁▂▁ "),
+    ),
+];
+const SHOT_BURST_FRAMES: [(Sprite, Sprite); 2] = [
+    (Sprite::new("⢎", "*", "▒"), Sprite::EMPTY),
+    (Sprite::new("⠡", ".", "░"), Sprite::EMPTY),
+];
+
+impl EffectKind {
+    /// `(sprite_top, sprite_bottom)` of each frame
+    pub const fn frames(self) -> &'static [(Sprite, Sprite)] {
+        match self {
+            EffectKind::EnemyExplosion => &ENEMY_EXPLOSION_FRAMES,
+            EffectKind::PlayerExplosion => &PLAYER_EXPLOSION_FRAMES,
+            EffectKind::ShotBurst => &SHOT_BURST_FRAMES,
+        }
+    }
+
+    pub const fn width(self) -> u16 {
+        match self {
+            EffectKind::EnemyExplosion | EffectKind::PlayerExplosion => 5,
+            EffectKind::ShotBurst => 1,
+        }
+    }
+
+    /// Seconds each frame is shown
+    pub const fn frame_time(self) -> f32 {
+        match self {
+            EffectKind::EnemyExplosion => 0.1,
+            EffectKind::PlayerExplosion => 0.2,
+            EffectKind::ShotBurst => 0.08,
+        }
+    }
+
+    pub const fn style(self) -> Style {
+        match self {
+            EffectKind::EnemyExplosion => Style::fg(Color::Yellow).bold(),
+            EffectKind::PlayerExplosion => Style::fg(Color::Red).bold(),
+            EffectKind::ShotBurst => Style::fg(Color::White),
+        }
+    }
+
+    pub const fn net_code(self, frame: usize) -> u16 {
+        let base = match self {
+            EffectKind::EnemyExplosion => NET_EFFECT_ENEMY_EXPLOSION,
+            EffectKind::PlayerExplosion => NET_EFFECT_PLAYER_EXPLOSION,
+            EffectKind::ShotBurst => NET_EFFECT_SHOT_BURST,
+        };
+        base + frame as u16
+    }
+
+    /// Effect kind and frame of a replicated effect
+    pub fn from_net_code(code: u16) -> Option<(EffectKind, usize)> {
+        [
+            EffectKind::EnemyExplosion,
+            EffectKind::PlayerExplosion,
+            EffectKind::ShotBurst,
+        ]
+        .into_iter()
+        .find_map(|kind| {
+            let frame = code.checked_sub(kind.net_code(0))? as usize;
+            (frame < kind.frames().len()).then_some((kind, frame))
+        })
+    }
+}
+
+pub const UFO_SPRITE: Sprite = Sprite::new("⣠⣾⣿⣿⣷⣄", "_/##\\_", "▄▟██▙▄");
+const UFO_WIDTH: u16 = 6;
+const UFO_Y: u16 = FIELD_TOP;
+const UFO_MIN_INTERVAL: f32 = 20.0;
+const UFO_MAX_INTERVAL: f32 = 35.0;
+const UFO_POINTS: [i32; 4] = [50, 100, 150, 300];
+/// How long the awarded UFO score stays on screen
+const UFO_SCORE_TTL: f32 = 1.5;
+
+/// Chance of a destroyed invader dropping a power-up
+const POWER_UP_DROP_CHANCE: f64 = 0.08;
+/// Drops are picked evenly from this table, extra lives are the rarest
+const POWER_UP_TABLE: [PowerUpKind; 9] = [
+    PowerUpKind::RapidFire,
+    PowerUpKind::RapidFire,
+    PowerUpKind::SpreadShot,
+    PowerUpKind::SpreadShot,
+    PowerUpKind::Piercing,
+    PowerUpKind::Piercing,
+    PowerUpKind::Shield,
+    PowerUpKind::Shield,
+    PowerUpKind::ExtraLife,
+];
+const POWER_UP_FALL_SPEED: f32 = -10.0;
+const MAX_PLAYER_LIVES: u16 = 9;
+/// Seconds between shots while rapid fire is active
+const RAPID_FIRE_COOLDOWN: f32 = 0.15;
+/// Sideways cells the outer spread shots travel per cell they rise
+const SPREAD_SHOT_SLOPE: f32 = 0.35;
+pub const PLAYER_SHIELD_STYLE: Style = Style::fg(Color::Green).reverse();
+const COPLAYER_SHIELD_STYLE: Style = Style::fg(Color::Cyan).reverse();
+const PIERCING_SHOT_STYLE: Style = Style::fg(Color::White).bold();
+pub const POWER_UP_BOUNDS: BoundingBox = BoundingBox {
+    width: 1,
+    height: 1,
+    y_offset: 0,
+    layer: LAYER_POWER_UP,
+    mask: LAYER_PLAYER,
+};
+
+impl PowerUpKind {
+    pub const fn sprite(self) -> Sprite {
+        match self {
+            PowerUpKind::RapidFire => Sprite::text("R"),
+            PowerUpKind::SpreadShot => Sprite::text("S"),
+            PowerUpKind::Piercing => Sprite::text("P"),
+            PowerUpKind::Shield => Sprite::text("O"),
+            PowerUpKind::ExtraLife => Sprite::text("+"),
+        }
+    }
+
+    pub const fn style(self) -> Style {
+        match self {
+            PowerUpKind::RapidFire => Style::fg(Color::Yellow).bold(),
+            PowerUpKind::SpreadShot => Style::fg(Color::Magenta).bold(),
+            PowerUpKind::Piercing => Style::fg(Color::White).bold(),
+            PowerUpKind::Shield => Style::fg(Color::Cyan).bold(),
+            PowerUpKind::ExtraLife => Style::fg(Color::Red).bold(),
+        }
+    }
+
+    /// Seconds the effect lasts, zero for instant effects
+    pub const fn duration(self) -> f32 {
+        match self {
+            PowerUpKind::RapidFire => 10.0,
+            PowerUpKind::SpreadShot => 10.0,
+            PowerUpKind::Piercing => 6.0,
+            PowerUpKind::Shield => 8.0,
+            PowerUpKind::ExtraLife => 0.0,
+        }
+    }
+
+    pub const fn net_code(self) -> u16 {
+        match self {
+            PowerUpKind::RapidFire => NET_POWER_UP_RAPID_FIRE,
+            PowerUpKind::SpreadShot => NET_POWER_UP_SPREAD_SHOT,
+            PowerUpKind::Piercing => NET_POWER_UP_PIERCING,
+            PowerUpKind::Shield => NET_POWER_UP_SHIELD,
+            PowerUpKind::ExtraLife => NET_POWER_UP_EXTRA_LIFE,
+        }
+    }
+
+    pub const fn from_net_code(code: u16) -> Option<PowerUpKind> {
+        match code {
+            NET_POWER_UP_RAPID_FIRE => Some(PowerUpKind::RapidFire),
+            NET_POWER_UP_SPREAD_SHOT => Some(PowerUpKind::SpreadShot),
+            NET_POWER_UP_PIERCING => Some(PowerUpKind::Piercing),
+            NET_POWER_UP_SHIELD => Some(PowerUpKind::Shield),
+            NET_POWER_UP_EXTRA_LIFE => Some(PowerUpKind::ExtraLife),
+            _ => None,
+        }
+    }
+}
+
+pub fn create_world() -> GameState {
+    create_world_with_waves(WaveSet::builtin())
+}
+
+pub fn create_world_with_waves(waves: WaveSet) -> GameState {
+    create_world_with_settings(waves, Settings::default())
+}
+
+pub fn create_world_with_settings(waves: WaveSet, settings: Settings) -> GameState {
     let mut world = World::new();
+    let preset = settings.difficulty.preset();
 
     let player_entity = world.spawn((
         Player,
         Position { x: 55, y: 7 },
-        PrevPosition { x: 55, y: 7 },
         Velocity {
-            speed: 60.0,
+            speed: preset.player_speed,
             move_accumulator: 0.0,
             direction: Direction::None,
         },
         Renderable {
-            sprite_top: "⣆⡜⣛⢣⣠",
-            sprite_bottom: "⣿⣿⣿⣿⣿",
+            sprite_top: PLAYER_SPRITE.0,
+            sprite_bottom: PLAYER_SPRITE.1,
             width: 5,
-            destroy: false,
-            erased: false,
+            style: PLAYER_STYLE,
         },
+        PLAYER_BOUNDS,
+        Collides::LoseLife,
+        PowerUps::default(),
     ));
 
     // Each frame is a list of lines
     let mut game_state = GameState {
         world,
-        player_lives: 3,
+        player_lives: settings.starting_lives,
+        player_hit_timer: 0.0,
         player_entity,
         player_projectile_exists: false,
         enemy_direction: Direction::Right,
-        score_updated: true,
         score: 0,
         high_score: 0,
+        new_high_score: false,
+        high_scores: HighScores::default(),
+        difficulty: settings.difficulty,
+        custom_lives: settings.starting_lives != preset.starting_lives,
         enemy_speed_multiplier: 1.0,
         enemy_proj_prob_multiplier: 1.0,
-        enemy_amount: 30,
+        enemy_amount: 0,
+        waves,
+        wave: 1,
+        boss_wave: false,
+        formation_size: 0,
+        formation_frame: 0,
+        formation_cells: 0,
+        max_divers: 0,
+        dive_timer: DIVE_MAX_INTERVAL,
+        wave_banner_timer: WAVE_BANNER_TIME,
+        wave_errors: Vec::new(),
+        keymap: Keymap::default(),
+        keymap_errors: Vec::new(),
+        settings,
+        settings_errors: Vec::new(),
+        ufo_timer: UFO_MAX_INTERVAL,
+        collision_events: Vec::new(),
         game_over: false,
         game_over_notifier: false,
         paused: false,
         pause_notifier: false,
+        pause_menu: PauseMenu::default(),
+        resume: None,
         restart_notifier: false,
         player_input_handler: PlayerInputHandler {
             player_shoot: false,
             move_player_right: false,
             move_player_left: false,
+            mouse_target: None,
         },
         coplayer_handler: CoPlayerHandler {
             exists: false,
             player_shoot: false,
             x: 55,
             projectile_exists: false,
+            move_left: false,
+            move_right: false,
+            shoot_held: false,
             host_entities: Option::None,
-            old_host_entities: Option::None,
+            host_bunkers: Vec::new(),
         },
+        local_coop: false,
+        controls_menu: ControlsMenu::default(),
+        settings_menu: SettingsMenu::default(),
         main_menu: MainMenu {
             active_menu_item: MenuItem::HostGame,
             screen: Screen::Main,
+            message: None,
         },
         networking: GameNetworking {
             stay_online: false,
             host: false,
             peer: Option::None,
-            connection_task: Option::None,
-            tx_writer: Option::None,
+            listening: false,
+            connected: false,
+            outbox: Vec::new(),
         },
         request_clear_render: false,
+        rng: StdRng::from_os_rng(),
     };
 
-    spawn_enemies(
-        game_state.enemy_proj_prob_multiplier,
-        game_state.enemy_speed_multiplier,
-        &mut game_state.world,
-    );
-    let stdout = stdout();
-
-    let renderer = Render {
-        stdout,
-        wsize: terminal::window_size()?,
-        wsize_updated: true,
-    };
+    spawn_bunkers(&mut game_state.world);
+    start_wave(&mut game_state);
 
-    Ok((game_state, renderer))
+    game_state
 }
 
-pub fn restart_world(high_score: i32) -> Result<(GameState, Render), Box<dyn Error>> {
-    let mut world = World::new();
-
-    let player_entity = world.spawn((
-        Player,
-        Position { x: 55, y: 7 },
-        PrevPosition { x: 55, y: 7 },
-        Velocity {
-            speed: 60.0,
-            move_accumulator: 0.0,
-            direction: Direction::None,
-        },
-        Renderable {
-            sprite_top: "⣆⡜⣛⢣⣠",
-            sprite_bottom: "⣿⣿⣿⣿⣿",
-            width: 5,
-            destroy: false,
-            erased: false,
-        },
-    ));
+/// Starts a new game from the settings, carrying the high score over
+pub fn restart_world(high_score: i32, waves: WaveSet, settings: Settings) -> GameState {
+    let mut game_state = create_world_with_settings(waves, settings);
+    game_state.high_score = high_score;
+    game_state.main_menu.screen = Screen::Game;
+    game_state
+}
 
-    // Each frame is a list of lines
-    let mut game_state = GameState {
-        world,
-        player_lives: 3,
-        player_entity,
-        player_projectile_exists: false,
-        enemy_direction: Direction::Right,
-        score_updated: true,
-        score: 0,
-        high_score,
-        enemy_speed_multiplier: 1.0,
-        enemy_proj_prob_multiplier: 1.0,
-        enemy_amount: 30,
-        game_over: false,
-        game_over_notifier: false,
-        paused: false,
-        pause_notifier: false,
-        restart_notifier: false,
-        player_input_handler: PlayerInputHandler {
-            player_shoot: false,
-            move_player_right: false,
-            move_player_left: false,
-        },
-        coplayer_handler: CoPlayerHandler {
-            exists: false,
-            player_shoot: false,
-            projectile_exists: false,
-            x: 55,
-            host_entities: Option::None,
-            old_host_entities: Option::None,
-        },
-        main_menu: MainMenu {
-            active_menu_item: MenuItem::HostGame,
-            screen: Screen::Game,
-        },
-        networking: GameNetworking {
-            stay_online: false,
-            host: false,
-            peer: Option::None,
-            connection_task: Option::None,
-            tx_writer: Option::None,
-        },
-        request_clear_render: false,
+/// Continues a saved game at the start of the wave it was saved in
+pub fn resume_world(
+    high_score: i32,
+    waves: WaveSet,
+    settings: Settings,
+    save: &SaveGame,
+) -> GameState {
+    let saved_settings = Settings {
+        difficulty: save.difficulty,
+        ..settings.clone()
     };
-    spawn_enemies(
-        game_state.enemy_proj_prob_multiplier,
-        game_state.enemy_speed_multiplier,
-        &mut game_state.world,
-    );
+    let mut game_state = restart_world(high_score, waves, saved_settings);
+    game_state.settings = settings;
 
-    let stdout = stdout();
+    // Drop the first wave's formation, the saved one spawns once its banner is gone
+    let enemies: Vec<Entity> = game_state
+        .world
+        .query_mut::<()>()
+        .with::<&Enemy>()
+        .into_iter()
+        .map(|(id, ())| id)
+        .collect();
+    for enemy in enemies {
+        let _ = game_state.world.despawn(enemy);
+    }
+    game_state.enemy_amount = 0;
+    game_state.wave = save.wave.max(1);
+    game_state.boss_wave = save.boss_wave;
+    game_state.score = save.score;
+    game_state.player_lives = save.player_lives.max(1);
+    game_state.custom_lives = save.custom_lives;
+    game_state.wave_banner_timer = WAVE_BANNER_TIME;
+    game_state
+}
 
-    let renderer = Render {
-        stdout,
-        wsize: terminal::window_size()?,
-        wsize_updated: true,
-    };
+fn spawn_bunkers(world: &mut World) {
+    for left in BUNKER_X {
+        for (row, line) in BUNKER_SHAPE.iter().enumerate() {
+            for (column, ch) in line.chars().enumerate() {
+                if ch != '#' {
+                    continue;
+                }
+                world.spawn((
+                    BunkerCell,
+                    Health { hp: BUNKER_CELL_HP },
+                    Position {
+                        x: left + column as u16,
+                        y: BUNKER_TOP - row as u16,
+                    },
+                    Renderable {
+                        sprite_top: bunker_sprite(BUNKER_CELL_HP),
+                        sprite_bottom: Sprite::EMPTY,
+                        width: 1,
+                        style: BUNKER_STYLE,
+                    },
+                    BUNKER_CELL_BOUNDS,
+                    Collides::LoseHP,
+                ));
+            }
+        }
+    }
+}
 
-    Ok((game_state, renderer))
+/// Glyph of a bunker cell, thinning out as it loses hit points
+pub const fn bunker_sprite(hp: u8) -> Sprite {
+    match hp {
+        0 => Sprite::text(" "),
+        1 => Sprite::new("⠡", ".", "░"),
+        2 => Sprite::new("⡵", "+", "▒"),
+        _ => Sprite::new("⣿", "#", "█"),
+    }
 }
 
-fn spawn_enemies(proj_multiplier: f32, speed_multiplier: f32, world: &mut World) {
-    for x in 0..10 {
-        for y in 0..3 {
-            world.spawn((
+/// Spawns the formation of the current wave and applies its settings and events
+fn start_wave(game_state: &mut GameState) {
+    if game_state.boss_wave {
+        spawn_boss(game_state);
+        return;
+    }
+
+    let preset = game_state.difficulty.preset();
+    let wave = game_state
+        .waves
+        .scaled_wave(game_state.wave, preset.wave_scaling);
+
+    game_state.enemy_speed_multiplier = wave.speed * preset.enemy_speed;
+    game_state.enemy_proj_prob_multiplier = wave.fire_rate * preset.enemy_fire_rate;
+    game_state.enemy_amount = wave.enemy_count();
+    game_state.formation_size = wave.enemy_count();
+    game_state.enemy_direction = Direction::Right;
+    game_state.max_divers = wave.divers;
+
+    for event in &wave.events {
+        match event {
+            WaveEvent::Ufo => game_state.ufo_timer = 0.0,
+            WaveEvent::RebuildBunkers => {
+                let cells: Vec<Entity> = game_state
+                    .world
+                    .query_mut::<()>()
+                    .with::<&BunkerCell>()
+                    .into_iter()
+                    .map(|(id, ())| id)
+                    .collect();
+                for cell in cells {
+                    let _ = game_state.world.despawn(cell);
+                }
+                spawn_bunkers(&mut game_state.world);
+            }
+        }
+    }
+
+    for (row, kinds) in wave.formation.iter().enumerate() {
+        for (column, kind) in kinds.iter().enumerate() {
+            let Some(kind) = *kind else {
+                continue;
+            };
+            let stats = kind.stats();
+            let (sprite_top, sprite_bottom) = stats.frames[game_state.formation_frame];
+            game_state.world.spawn((
                 Enemy,
+                kind,
+                Health { hp: stats.hp },
                 Position {
-                    x: 6 + x * 7,
-                    y: 38 - y * 4,
-                },
-                PrevPosition {
-                    x: 6 + x * 7,
-                    y: 38 - y * 4,
+                    x: 6 + column as u16 * 7,
+                    y: 38 - row as u16 * 4,
                 },
                 Renderable {
-                    sprite_top: "⢳⡴⠶⢦⡞",
-                    sprite_bottom: "⠞⠫⡪⠋⠱",
+                    sprite_top,
+                    sprite_bottom,
                     width: 5,
-                    destroy: false,
-                    erased: false,
+                    style: stats.style,
                 },
                 Velocity {
-                    speed: 20.0 * speed_multiplier,
+                    speed: 20.0 * game_state.enemy_speed_multiplier,
                     move_accumulator: 0.0,
                     direction: Direction::None, // Enemy directon is stored in game state
                 },
                 ProjectileSpawner {
-                    probability: 0.1 * proj_multiplier as f64,
+                    probability: stats.fire_probability
+                        * game_state.enemy_proj_prob_multiplier as f64,
                     projectile_speed: -20.0,
                 },
+                ENEMY_BOUNDS,
+                Collides::LoseHP,
             ));
         }
     }
 }
 
-pub fn process_tick(
-    delta_time: Duration,
-    game_state: &mut GameState,
-) -> Result<(), Box<dyn Error>> {
-    move_player(delta_time, &mut game_state.world);
-    process_player_projectile(delta_time, game_state)?;
-
-    process_enemies(delta_time, game_state);
-    enemy_collision_detection(game_state);
-
-    process_enemy_projectiles(delta_time, game_state)?;
-    player_collision_detection(game_state);
-
-    entity_cleanup(&mut game_state.world)?;
-
-    Ok(())
-}
+fn spawn_boss(game_state: &mut GameState) {
+    let hp = game_state.waves.boss_hp;
+    let x = (SCREEN_WIDTH - BOSS_WIDTH) / 2;
+    game_state.enemy_amount = 1;
+
+    let boss = game_state.world.spawn((
+        Boss {
+            hp,
+            max_hp: hp,
+            phase: BOSS_SCRIPT[0].0,
+            script_step: 0,
+            phase_time: 0.0,
+            strafe_time: 0.0,
+            fire_timer: BOSS_STRAFE_FIRE_INTERVAL,
+        },
+        Position { x, y: BOSS_TOP },
+    ));
 
-pub fn process_multiplayer(
-    delta_time: Duration,
-    game_state: &mut GameState,
-) -> Result<(), Box<dyn Error>> {
-    if !game_state.coplayer_handler.exists {
+    for (offset_x, offset_y, sprite_top, sprite_bottom, weak_point) in BOSS_PARTS {
         game_state.world.spawn((
-            CoPlayer,
-            Position { x: 55, y: 7 },
-            PrevPosition { x: 55, y: 7 },
-            Velocity {
-                speed: 60.0,
-                move_accumulator: 0.0,
-                direction: Direction::None,
+            BossPart {
+                boss,
+                offset_x,
+                offset_y,
+                weak_point,
+            },
+            Position {
+                x: x + offset_x,
+                y: BOSS_TOP - offset_y,
             },
             Renderable {
-                sprite_top: "⣆⡜⣛⢣⣠",
-                sprite_bottom: "⣿⣿⣿⣿⣿",
+                sprite_top,
+                sprite_bottom,
                 width: 5,
-                destroy: false,
-                erased: false,
+                style: if weak_point {
+                    BOSS_WEAK_POINT_STYLE
+                } else {
+                    BOSS_STYLE
+                },
             },
+            BOSS_PART_BOUNDS,
+            Collides::Pierce,
         ));
-        game_state.coplayer_handler.exists = true;
     }
+}
 
-    for (_, (pos, prevpos)) in game_state
+/// Moves the boss along its script, fires and keeps its parts attached
+fn process_boss(delta_time: Duration, game_state: &mut GameState) {
+    let dt = delta_time.as_secs_f32();
+    let cannons: Vec<Position> = game_state
         .world
-        .query_mut::<(&mut Position, &mut PrevPosition)>()
-        .with::<&CoPlayer>()
-    {
-        prevpos.x = pos.x;
-        pos.x = game_state.coplayer_handler.x;
-    }
-
-    move_player(delta_time, &mut game_state.world);
-
-    if game_state.networking.host {
-        process_player_projectile(delta_time, game_state)?;
-        process_coplayer_projectile(delta_time, game_state)?;
-
-        process_enemies(delta_time, game_state);
-        enemy_collision_detection(game_state);
-
-        process_enemy_projectiles(delta_time, game_state)?;
-        player_collision_detection(game_state);
-
-        entity_cleanup(&mut game_state.world)?;
-    }
+        .query_mut::<&Position>()
+        .with::<hecs::Or<&Player, &CoPlayer>>()
+        .into_iter()
+        .map(|(_, pos)| *pos)
+        .collect();
+
+    let mut shots: Vec<(Position, Option<Drift>)> = Vec::new();
+    let mut anchors: Vec<(Entity, Position)> = Vec::new();
+
+    for (id, (boss, pos)) in game_state.world.query_mut::<(&mut Boss, &mut Position)>() {
+        boss.phase_time += dt;
+        boss.fire_timer -= dt;
+
+        if boss.phase_time >= BOSS_SCRIPT[boss.script_step].1 {
+            boss.script_step = (boss.script_step + 1) % BOSS_SCRIPT.len();
+            boss.phase = BOSS_SCRIPT[boss.script_step].0;
+            boss.phase_time = 0.0;
+        }
 
-    let shooting = game_state.coplayer_handler.player_shoot;
-    game_state.coplayer_handler.player_shoot = false;
+        let left = FIELD_MARGIN as f32;
+        let right = (SCREEN_WIDTH - FIELD_MARGIN - BOSS_WIDTH) as f32;
+        let center = (left + right) / 2.0;
+        let amplitude = (right - left) / 2.0;
 
-    match game_state.networking.tx_writer {
-        Some(ref tx_writer) => {
-            if let Some((_, pos)) = game_state
-                .world
-                .query::<&Position>()
-                .with::<&Player>()
-                .iter()
-                .map(|(id, pos)| (id, *pos))
-                .next()
-            {
-                tx_writer.send(NetPacket::PlayerInput {
-                    x: pos.x as f32,
-                    shoot: shooting,
-                })?;
+        match boss.phase {
+            BossPhase::Strafe => {
+                boss.strafe_time += dt;
+                pos.y = BOSS_TOP;
             }
-
-            if game_state.networking.host {
-                let mut entities: Vec<(u16, u16, u16)> = Vec::new();
-
-                for (_, pos) in game_state.world.query_mut::<&Position>().with::<&Enemy>() {
-                    entities.push((0, pos.x, pos.y));
-                }
-
-                for (_, pos) in game_state
-                    .world
-                    .query_mut::<&Position>()
-                    .with::<&EnemyProjectile>()
-                {
-                    entities.push((1, pos.x, pos.y));
-                }
-
-                for (_, pos) in game_state
-                    .world
-                    .query_mut::<&Position>()
-                    .with::<&PlayerProjectile>()
-                {
-                    entities.push((1, pos.x, pos.y));
-                }
-
-                for (_, pos) in game_state.world.query_mut::<&Position>().with::<&Player>() {
-                    entities.push((2, pos.x, pos.y));
+            BossPhase::Burst => pos.y = BOSS_TOP,
+            BossPhase::Dive => {
+                let duration = BOSS_SCRIPT[boss.script_step].1;
+                let depth = (std::f32::consts::PI * boss.phase_time / duration).sin();
+                pos.y = BOSS_TOP - (depth.max(0.0) * BOSS_DIVE_DEPTH) as u16;
+            }
+        }
+        let sweep = (boss.strafe_time * BOSS_STRAFE_FREQUENCY).sin();
+        pos.x = (center + sweep * amplitude).round() as u16;
+
+        // Projectiles leave from below the core
+        let muzzle = Position {
+            x: pos.x + BOSS_WIDTH / 2,
+            y: pos.y - 4,
+        };
+        if boss.fire_timer <= 0.0 {
+            match boss.phase {
+                BossPhase::Strafe => {
+                    boss.fire_timer = BOSS_STRAFE_FIRE_INTERVAL;
+                    let drift = aim_at_nearest(&muzzle, BOSS_PROJECTILE_SPEED.abs(), &cannons);
+                    shots.push((muzzle, drift));
                 }
-                for (_, pos) in game_state
-                    .world
-                    .query_mut::<&Position>()
-                    .with::<&CoPlayer>()
-                {
-                    entities.push((2, pos.x, pos.y));
+                BossPhase::Burst => {
+                    boss.fire_timer = BOSS_BURST_FIRE_INTERVAL;
+                    for slope in BOSS_BURST_SLOPES {
+                        let drift = (slope != 0.0).then_some(Drift {
+                            speed: slope * BOSS_PROJECTILE_SPEED.abs(),
+                            move_accumulator: 0.0,
+                        });
+                        shots.push((muzzle, drift));
+                    }
                 }
-
-                tx_writer.send(NetPacket::GameStateUpdate { entities })?;
+                BossPhase::Dive => boss.fire_timer = 0.0,
             }
         }
-        _ => {
-            game_state.exit_to_menu();
-        }
-    }
-    if let Some((_, mut pos)) = game_state
-        .world
-        .query::<&Position>()
-        .with::<&CoPlayer>()
-        .iter()
-        .map(|(id, pos)| (id, *pos))
-        .next()
-    {
-        pos.x = game_state.coplayer_handler.x;
-    }
-    Ok(())
-}
 
-fn spawn_player_projectile(game_state: &mut GameState) {
-    if game_state.player_projectile_exists || !game_state.player_input_handler.player_shoot {
-        return;
-    } else {
-        game_state.player_projectile_exists = true;
+        anchors.push((id, *pos));
     }
 
-    let mut pos: Option<u16> = Option::None;
-    if let Ok(position) = game_state
-        .world
-        .query_one_mut::<&Position>(game_state.player_entity)
-    {
-        pos = Option::Some(position.x);
+    for (_, (part, pos)) in game_state.world.query_mut::<(&BossPart, &mut Position)>() {
+        if let Some((_, anchor)) = anchors.iter().find(|(id, _)| *id == part.boss) {
+            pos.x = anchor.x + part.offset_x;
+            pos.y = anchor.y - part.offset_y;
+        }
     }
 
-    if let Some(pos) = pos {
-        game_state.world.spawn((
-            PlayerProjectile,
-            // We add 2 to pos, as width of player is 5 and we want projectiles to spawn in
-            // the middle
-            Position { x: pos + 2, y: 8 },
-            PrevPosition { x: pos + 2, y: 8 },
+    for (pos, drift) in shots {
+        let projectile = game_state.world.spawn((
+            EnemyProjectile,
+            pos,
             Velocity {
-                speed: 60.0,
+                speed: BOSS_PROJECTILE_SPEED,
                 move_accumulator: 0.0,
                 direction: Direction::None,
             },
             Renderable {
-                sprite_top: "⣿",
-                sprite_bottom: "",
+                sprite_top: Sprite::EMPTY,
+                sprite_bottom: ENEMY_SHOT_SPRITE,
                 width: 1,
-                destroy: false,
-                erased: false,
+                style: ENEMY_PROJECTILE_STYLE,
             },
+            ENEMY_SHOT_BOUNDS,
+            Collides::DestroySelf,
         ));
+        if let Some(drift) = drift {
+            let _ = game_state.world.insert_one(projectile, drift);
+        }
     }
 }
 
-fn spawn_coplayer_projectile(game_state: &mut GameState) {
-    if game_state.coplayer_handler.projectile_exists || !game_state.coplayer_handler.player_shoot {
-        game_state.coplayer_handler.player_shoot = false;
-        return;
-    } else {
-        game_state.coplayer_handler.projectile_exists = true;
-        game_state.coplayer_handler.player_shoot = false;
-    }
+/// Takes boss health for every hit on a weak point and ends the fight at zero
+fn damage_boss(game_state: &mut GameState) {
+    let mut defeated: Vec<Entity> = Vec::new();
 
-    let mut pos: Option<u16> = Option::None;
-    for (_, position) in game_state
-        .world
-        .query_mut::<&Position>()
-        .with::<&CoPlayer>()
-    {
-        pos = Option::Some(position.x);
+    for event in &game_state.collision_events {
+        let Ok(boss_id) = game_state
+            .world
+            .get::<&BossPart>(event.entity)
+            .map(|part| part.weak_point.then_some(part.boss))
+        else {
+            continue;
+        };
+        let Some(boss_id) = boss_id else {
+            continue;
+        };
+
+        if let Ok(mut boss) = game_state.world.get::<&mut Boss>(boss_id)
+            && boss.hp > 0
+        {
+            boss.hp -= 1;
+            if boss.hp == 0 {
+                defeated.push(boss_id);
+            }
+        }
     }
 
-    if let Some(pos) = pos {
-        game_state.world.spawn((
-            PlayerProjectile,
-            CoPlayerProjectile,
-            // We add 2 to pos, as width of player is 5 and we want projectiles to spawn in
-            // the middle
-            Position { x: pos + 2, y: 8 },
-            PrevPosition { x: pos + 2, y: 8 },
-            Velocity {
-                speed: 60.0,
-                move_accumulator: 0.0,
-                direction: Direction::None,
-            },
-            Renderable {
-                sprite_top: "⣿",
-                sprite_bottom: "",
-                width: 1,
-                destroy: false,
-                erased: false,
-            },
-        ));
+    for boss_id in defeated {
+        let parts: Vec<Entity> = game_state
+            .world
+            .query_mut::<&BossPart>()
+            .into_iter()
+            .filter(|(_, part)| part.boss == boss_id)
+            .map(|(id, _)| id)
+            .collect();
+        for entity_id in parts.into_iter().chain([boss_id]) {
+            let _ = game_state.world.insert_one(entity_id, Despawn);
+        }
+
+        game_state.score += BOSS_POINTS;
+        game_state.enemy_amount = 0;
+        game_state.boss_wave = false;
+        game_state.wave += 1;
+        game_state.wave_banner_timer = WAVE_BANNER_TIME;
     }
 }
 
-fn process_coplayer_projectile(
-    delta_time: Duration,
+/// Counts down the "Wave N" banner and starts the next wave when it is over
+fn process_waves(delta_time: Duration, game_state: &mut GameState) {
+    if game_state.wave_banner_timer <= 0.0 {
+        return;
+    }
+
+    game_state.wave_banner_timer -= delta_time.as_secs_f32();
+    if game_state.wave_banner_timer <= 0.0 && game_state.enemy_amount == 0 {
+        start_wave(game_state);
+    }
+}
+
+/// Adds the co-player's cannon once a co-op game starts
+fn spawn_coplayer(game_state: &mut GameState) {
+    if game_state.coplayer_handler.exists {
+        return;
+    }
+    game_state.world.spawn((
+        CoPlayer,
+        Position { x: 55, y: 7 },
+        Velocity {
+            speed: game_state.difficulty.preset().player_speed,
+            move_accumulator: 0.0,
+            direction: Direction::None,
+        },
+        Renderable {
+            sprite_top: PLAYER_SPRITE.0,
+            sprite_bottom: PLAYER_SPRITE.1,
+            width: 5,
+            style: COPLAYER_STYLE,
+        },
+        PLAYER_BOUNDS,
+        Collides::LoseLife,
+        PowerUps::default(),
+    ));
+    game_state.coplayer_handler.exists = true;
+}
+
+/// Applies the keys held by the co-player in local co-op
+fn steer_coplayer(game_state: &mut GameState) {
+    let handler = &mut game_state.coplayer_handler;
+    // Fires for as long as the key is held, like the player's shoot key
+    handler.player_shoot |= handler.shoot_held;
+
+    for (_, vel) in game_state
+        .world
+        .query_mut::<&mut Velocity>()
+        .with::<&CoPlayer>()
+    {
+        vel.direction = match (handler.move_left, handler.move_right) {
+            (true, false) => Direction::Left,
+            (false, true) => Direction::Right,
+            _ => Direction::None,
+        };
+    }
+}
+
+pub fn process_tick(
+    delta_time: Duration,
     game_state: &mut GameState,
 ) -> Result<(), Box<dyn Error>> {
-    spawn_coplayer_projectile(game_state);
+    if game_state.local_coop {
+        spawn_coplayer(game_state);
+        steer_coplayer(game_state);
+    }
+    steer_player(game_state);
+    move_player(
+        delta_time,
+        &mut game_state.world,
+        game_state.player_input_handler.mouse_target,
+    );
+    process_player_projectile(delta_time, game_state)?;
+    if game_state.local_coop {
+        process_coplayer_projectile(delta_time, game_state)?;
+    }
+
+    process_enemies(delta_time, game_state);
+    process_divers(delta_time, game_state);
+    process_enemy_projectiles(delta_time, game_state)?;
+    process_ufo(delta_time, game_state);
+    process_power_ups(delta_time, game_state);
+    process_boss(delta_time, game_state);
+
+    collision_detection(game_state);
+    spawn_explosions(game_state);
+    erode_bunkers(game_state);
+    score_kills(game_state);
+    damage_boss(game_state);
+    collect_power_ups(game_state);
+    damage_players(game_state);
+    process_waves(delta_time, game_state);
+    update_player_flash(delta_time, game_state);
+    update_floating_texts(delta_time, game_state);
+    update_effects(delta_time, game_state);
+
+    entity_cleanup(game_state)?;
+
+    Ok(())
+}
+
+pub fn process_multiplayer(
+    delta_time: Duration,
+    game_state: &mut GameState,
+) -> Result<(), Box<dyn Error>> {
+    spawn_coplayer(game_state);
+
+    for (_, pos) in game_state
+        .world
+        .query_mut::<&mut Position>()
+        .with::<&CoPlayer>()
+    {
+        pos.x = game_state.coplayer_handler.x;
+    }
+
+    steer_player(game_state);
+    move_player(
+        delta_time,
+        &mut game_state.world,
+        game_state.player_input_handler.mouse_target,
+    );
+
+    if game_state.networking.host {
+        process_player_projectile(delta_time, game_state)?;
+        process_coplayer_projectile(delta_time, game_state)?;
+
+        process_enemies(delta_time, game_state);
+        process_divers(delta_time, game_state);
+        process_enemy_projectiles(delta_time, game_state)?;
+        process_ufo(delta_time, game_state);
+        process_power_ups(delta_time, game_state);
+        process_boss(delta_time, game_state);
+
+        collision_detection(game_state);
+        spawn_explosions(game_state);
+        erode_bunkers(game_state);
+        score_kills(game_state);
+        damage_boss(game_state);
+        collect_power_ups(game_state);
+        damage_players(game_state);
+        process_waves(delta_time, game_state);
+        update_player_flash(delta_time, game_state);
+        update_floating_texts(delta_time, game_state);
+        update_effects(delta_time, game_state);
+
+        entity_cleanup(game_state)?;
+    }
+
+    let shooting = game_state.coplayer_handler.player_shoot;
+    game_state.coplayer_handler.player_shoot = false;
+
+    if game_state.networking.connected {
+        if let Some((_, pos)) = game_state
+            .world
+            .query::<&Position>()
+            .with::<&Player>()
+            .iter()
+            .map(|(id, pos)| (id, *pos))
+            .next()
+        {
+            game_state.networking.outbox.push(NetPacket::PlayerInput {
+                x: pos.x as f32,
+                shoot: shooting,
+            });
+        }
+
+        if game_state.networking.host {
+            let mut entities: Vec<(u16, u16, u16)> =
+                vec![(NET_FORMATION_FRAME, game_state.formation_frame as u16, 0)];
+
+            for (_, (pos, kind)) in game_state
+                .world
+                .query_mut::<(&Position, &EnemyKind)>()
+                .with::<&Enemy>()
+            {
+                entities.push((kind.net_code(), pos.x, pos.y));
+            }
+
+            for (_, pos) in game_state
+                .world
+                .query_mut::<&Position>()
+                .with::<&EnemyProjectile>()
+            {
+                entities.push((NET_ENEMY_PROJECTILE, pos.x, pos.y));
+            }
+
+            for (_, pos) in game_state
+                .world
+                .query_mut::<&Position>()
+                .with::<&PlayerProjectile>()
+                .without::<&CoPlayerProjectile>()
+            {
+                entities.push((NET_PLAYER_PROJECTILE, pos.x, pos.y));
+            }
+
+            for (_, pos) in game_state
+                .world
+                .query_mut::<&Position>()
+                .with::<&CoPlayerProjectile>()
+            {
+                entities.push((NET_COPLAYER_PROJECTILE, pos.x, pos.y));
+            }
+
+            for (_, pos) in game_state.world.query_mut::<&Position>().with::<&Player>() {
+                entities.push((NET_PLAYER, pos.x, pos.y));
+            }
+            for (_, pos) in game_state
+                .world
+                .query_mut::<&Position>()
+                .with::<&CoPlayer>()
+            {
+                entities.push((NET_COPLAYER, pos.x, pos.y));
+            }
+            for (_, pos) in game_state.world.query_mut::<&Position>().with::<&Ufo>() {
+                entities.push((NET_UFO, pos.x, pos.y));
+            }
+            for (_, (pos, part)) in game_state.world.query_mut::<(&Position, &BossPart)>() {
+                if let Some(index) = BOSS_PARTS
+                    .iter()
+                    .position(|(x, y, ..)| (*x, *y) == (part.offset_x, part.offset_y))
+                {
+                    entities.push((NET_BOSS_PART + index as u16, pos.x, pos.y));
+                }
+            }
+            for (_, boss) in game_state.world.query_mut::<&Boss>() {
+                entities.push((NET_BOSS_HEALTH, boss.hp, boss.max_hp));
+            }
+            for (_, (pos, power_up)) in game_state.world.query_mut::<(&Position, &PowerUp)>() {
+                entities.push((power_up.kind.net_code(), pos.x, pos.y));
+            }
+            for (_, (pos, effect)) in game_state.world.query_mut::<(&Position, &Effect)>() {
+                let frame = (effect.elapsed / effect.kind.frame_time()) as usize;
+                entities.push((effect.kind.net_code(frame), pos.x, pos.y));
+            }
+
+            let mut bunkers: Vec<(u16, u16, u8)> = Vec::new();
+            for (_, (pos, health)) in game_state
+                .world
+                .query_mut::<(&Position, &Health)>()
+                .with::<&BunkerCell>()
+            {
+                bunkers.push((pos.x, pos.y, health.hp));
+            }
+
+            game_state
+                .networking
+                .outbox
+                .push(NetPacket::GameStateUpdate { entities, bunkers });
+        }
+    } else {
+        game_state.exit_to_menu();
+    }
+    Ok(())
+}
+
+fn spawn_player_projectile(game_state: &mut GameState) {
+    if !game_state.player_input_handler.player_shoot {
+        return;
+    }
+
+    let Ok((x, power_ups)) = game_state
+        .world
+        .query_one_mut::<(&Position, &mut PowerUps)>(game_state.player_entity)
+        .map(|(pos, power_ups)| (pos.x, power_ups))
+    else {
+        return;
+    };
+    if !ready_to_fire(power_ups, game_state.player_projectile_exists) {
+        return;
+    }
+    let power_ups = *power_ups;
+
+    game_state.player_projectile_exists = true;
+    spawn_shots(&mut game_state.world, x, &power_ups, false);
+}
+
+fn spawn_coplayer_projectile(game_state: &mut GameState) {
+    if !game_state.coplayer_handler.player_shoot {
+        return;
+    }
+    game_state.coplayer_handler.player_shoot = false;
+
+    let Some((x, power_ups)) = game_state
+        .world
+        .query_mut::<(&Position, &mut PowerUps)>()
+        .with::<&CoPlayer>()
+        .into_iter()
+        .map(|(_, (pos, power_ups))| (pos.x, power_ups))
+        .next()
+    else {
+        return;
+    };
+    if !ready_to_fire(power_ups, game_state.coplayer_handler.projectile_exists) {
+        return;
+    }
+    let power_ups = *power_ups;
+
+    game_state.coplayer_handler.projectile_exists = true;
+    spawn_shots(&mut game_state.world, x, &power_ups, true);
+}
+
+/// One shot on screen at a time, rapid fire only waits for its cooldown
+fn ready_to_fire(power_ups: &mut PowerUps, projectile_exists: bool) -> bool {
+    if power_ups.rapid_fire > 0.0 {
+        if power_ups.fire_cooldown > 0.0 {
+            return false;
+        }
+        power_ups.fire_cooldown = RAPID_FIRE_COOLDOWN;
+        true
+    } else {
+        !projectile_exists
+    }
+}
+
+/// Fires from the cannon at `x`, shaped by the active power-ups
+fn spawn_shots(world: &mut World, x: u16, power_ups: &PowerUps, coplayer: bool) {
+    let slopes: &[f32] = if power_ups.spread_shot > 0.0 {
+        &[-SPREAD_SHOT_SLOPE, 0.0, SPREAD_SHOT_SLOPE]
+    } else {
+        &[0.0]
+    };
+    let piercing = power_ups.piercing > 0.0;
+    let speed = 60.0;
+
+    for &slope in slopes {
+        let style = if piercing {
+            PIERCING_SHOT_STYLE
+        } else if coplayer {
+            COPLAYER_STYLE
+        } else {
+            PLAYER_STYLE
+        };
+        let collides = if piercing {
+            Collides::Pierce
+        } else {
+            Collides::DestroySelf
+        };
+
+        let projectile = world.spawn((
+            PlayerProjectile,
+            // We add 2 to x, as width of player is 5 and we want projectiles to spawn in
+            // the middle
+            Position { x: x + 2, y: 8 },
+            Velocity {
+                speed,
+                move_accumulator: 0.0,
+                direction: Direction::None,
+            },
+            Renderable {
+                sprite_top: PLAYER_SHOT_SPRITE,
+                sprite_bottom: Sprite::EMPTY,
+                width: 1,
+                style,
+            },
+            PLAYER_SHOT_BOUNDS,
+            collides,
+        ));
+        if coplayer {
+            let _ = world.insert_one(projectile, CoPlayerProjectile);
+        }
+        if piercing {
+            let _ = world.insert_one(projectile, Pierced::default());
+        }
+        if slope != 0.0 {
+            let _ = world.insert_one(
+                projectile,
+                Drift {
+                    speed: slope * speed,
+                    move_accumulator: 0.0,
+                },
+            );
+        }
+    }
+}
 
-    // let mut player_projectile: Option<Entity> = Option::None;
-    // for (id, (pos, prev_pos, vel, renderable)) in game_state
-    //     .world
-    //     .query_mut::<(
-    //         &mut Position,
-    //         &mut PrevPosition,
-    //         &mut Velocity,
-    //         &mut Renderable,
-    //     )>()
-    //     .with::<&PlayerProjectile>()
-    // {
-    //     // projectile sprite was destroyed by renderer
-    //     if renderable.erased {
-    //         player_projectile = Option::Some(id);
-    //     }
-    //
-    //     vel.move_accumulator += vel.speed * delta_time.as_secs_f32();
-    //
-    //     if vel.move_accumulator >= 1.0 || vel.move_accumulator <= -1.0 {
-    //         // Move in whole-cell steps, keep fractional remainder to avoid drift and asymmetry
-    //         let steps = vel.move_accumulator.trunc();
-    //         let new_pos = pos.y as i32 + steps as i32;
-    //
-    //         let old_pos = pos.y;
-    //         prev_pos.y = old_pos;
-    //
-    //         if new_pos < 2 {
-    //             pos.y = 2;
-    //         } else if new_pos > 39 {
-    //             renderable.destroy = true;
-    //         } else {
-    //             pos.y = new_pos as u16;
-    //         }
-    //
-    //         vel.move_accumulator -= steps;
-    //     }
-    // }
-    //
-    // // If player projectile is assigned, we need to destory it
-    // if let Some(player_projectile) = player_projectile {
-    //     game_state.world.despawn(player_projectile)?;
-    //     game_state.player_projectile_exists = false;
-    // }
+fn process_coplayer_projectile(
+    _delta_time: Duration,
+    game_state: &mut GameState,
+) -> Result<(), Box<dyn Error>> {
+    // Co-player projectiles are also tagged `PlayerProjectile`, so they are moved
+    // by `process_player_projectile`
+    spawn_coplayer_projectile(game_state);
 
     Ok(())
 }
@@ -491,20 +1330,17 @@ fn process_player_projectile(
 ) -> Result<(), Box<dyn Error>> {
     spawn_player_projectile(game_state);
 
-    let mut player_projectile: Option<Entity> = Option::None;
-    for (id, (pos, prev_pos, vel, renderable)) in game_state
+    let mut projectiles_out: Vec<Entity> = Vec::new();
+    for (id, (pos, vel, drift)) in game_state
         .world
-        .query_mut::<(
-            &mut Position,
-            &mut PrevPosition,
-            &mut Velocity,
-            &mut Renderable,
-        )>()
+        .query_mut::<(&mut Position, &mut Velocity, Option<&mut Drift>)>()
         .with::<&PlayerProjectile>()
     {
-        // projectile sprite was destroyed by renderer
-        if renderable.erased {
-            player_projectile = Option::Some(id);
+        if let Some(drift) = drift
+            && !apply_drift(delta_time, pos, drift)
+        {
+            projectiles_out.push(id);
+            continue;
         }
 
         vel.move_accumulator += vel.speed * delta_time.as_secs_f32();
@@ -514,13 +1350,10 @@ fn process_player_projectile(
             let steps = vel.move_accumulator.trunc();
             let new_pos = pos.y as i32 + steps as i32;
 
-            let old_pos = pos.y;
-            prev_pos.y = old_pos;
-
             if new_pos < 2 {
                 pos.y = 2;
-            } else if new_pos > 39 {
-                renderable.destroy = true;
+            } else if new_pos > FIELD_TOP as i32 {
+                projectiles_out.push(id);
             } else {
                 pos.y = new_pos as u16;
             }
@@ -529,31 +1362,38 @@ fn process_player_projectile(
         }
     }
 
-    // If player projectile is assigned, we need to destory it
-    if let Some(player_projectile) = player_projectile {
-        // Check if it's a coplayer projectile before despawning
-        let is_coplayer_projectile = game_state
-            .world
-            .get::<&CoPlayerProjectile>(player_projectile)
-            .is_ok();
-
-        game_state.world.despawn(player_projectile)?;
-
-        if is_coplayer_projectile {
-            game_state.coplayer_handler.projectile_exists = false;
-        } else {
-            game_state.player_projectile_exists = false;
-        }
+    for proj in projectiles_out {
+        game_state.world.insert_one(proj, Despawn)?;
     }
 
     Ok(())
 }
 
-fn move_player(delta_time: Duration, world: &mut World) {
-    for (_id, (pos, prev_pos, vel)) in world
-        .query_mut::<(&mut Position, &mut PrevPosition, &mut Velocity)>()
-        .with::<&Player>()
+/// Points the cannon toward the mouse target, if it follows the mouse
+fn steer_player(game_state: &mut GameState) {
+    let Some(target) = game_state.player_input_handler.mouse_target else {
+        return;
+    };
+    let target = target.clamp(PLAYER_MIN_X, PLAYER_MAX_X);
+    if let Ok((pos, vel)) = game_state
+        .world
+        .query_one_mut::<(&Position, &mut Velocity)>(game_state.player_entity)
+    {
+        vel.direction = match pos.x.cmp(&target) {
+            Ordering::Less => Direction::Right,
+            Ordering::Greater => Direction::Left,
+            Ordering::Equal => Direction::None,
+        };
+    }
+}
+
+/// Moves the cannons. The player's stops at the mouse target instead of overshooting it.
+fn move_player(delta_time: Duration, world: &mut World, target: Option<u16>) {
+    for (_id, (pos, vel, player)) in world
+        .query_mut::<(&mut Position, &mut Velocity, Option<&Player>)>()
+        .with::<hecs::Or<&Player, &CoPlayer>>()
     {
+        let target = player.and(target);
         match vel.direction {
             Direction::Right => {
                 vel.move_accumulator += vel.speed * delta_time.as_secs_f32();
@@ -569,15 +1409,19 @@ fn move_player(delta_time: Duration, world: &mut World) {
         if vel.move_accumulator >= 1.0 || vel.move_accumulator <= -1.0 {
             // Move in whole-cell steps, keep fractional remainder to avoid drift and asymmetry
             let steps = vel.move_accumulator.trunc();
-            let new_pos = pos.x as i32 + steps as i32;
-
-            let old_pos = pos.x;
-            prev_pos.x = old_pos;
+            let mut new_pos = pos.x as i32 + steps as i32;
+            if let Some(target) = target.map(i32::from) {
+                new_pos = match vel.direction {
+                    Direction::Right => new_pos.min(target.max(pos.x as i32)),
+                    Direction::Left => new_pos.max(target.min(pos.x as i32)),
+                    Direction::None => new_pos,
+                };
+            }
 
-            if new_pos < 2 {
-                pos.x = 2;
-            } else if new_pos > 113 {
-                pos.x = 113;
+            if new_pos < PLAYER_MIN_X as i32 {
+                pos.x = PLAYER_MIN_X;
+            } else if new_pos > PLAYER_MAX_X as i32 {
+                pos.x = PLAYER_MAX_X;
             } else {
                 pos.x = new_pos as u16;
             }
@@ -587,26 +1431,63 @@ fn move_player(delta_time: Duration, world: &mut World) {
     }
 }
 
+/// Leftmost column, rightmost column and lowest row of the formation. Divers count
+/// with the slot they return to rather than where they swoop.
+fn formation_extent(world: &mut World) -> Option<(u16, u16, u16)> {
+    world
+        .query_mut::<(&Position, Option<&Diving>)>()
+        .with::<&Enemy>()
+        .into_iter()
+        .map(|(_, (pos, diving))| diving.map_or(*pos, |diving| diving.slot))
+        .fold(None, |extent, pos| {
+            let (left, right, bottom) = extent.unwrap_or((pos.x, pos.x, pos.y));
+            Some((left.min(pos.x), right.max(pos.x), bottom.min(pos.y)))
+        })
+}
+
 fn process_enemies(delta_time: Duration, game_state: &mut GameState) {
+    let Some((left, right, bottom)) = formation_extent(&mut game_state.world) else {
+        return;
+    };
     let mut enemies_hit_wall = false;
-    let mut projectiles_to_spawn: Vec<(Position, Velocity)> = Vec::new();
+    let mut marched = false;
+    let mut projectiles_to_spawn: Vec<(Position, Velocity, Option<Drift>)> = Vec::new();
+    let cannons: Vec<Position> = game_state
+        .world
+        .query_mut::<&Position>()
+        .with::<hecs::Or<&Player, &CoPlayer>>()
+        .into_iter()
+        .map(|(_, pos)| *pos)
+        .collect();
+
+    // The formation marches faster as it thins out
+    let remaining = game_state.enemy_amount as f32 / game_state.formation_size.max(1) as f32;
+    let speedup = game_state.waves.speedup_at(remaining);
 
-    for (_id, (pos, prev_pos, vel, proj_spawn)) in game_state
+    for (_id, (pos, vel, proj_spawn, kind, diving)) in game_state
         .world
         .query_mut::<(
             &mut Position,
-            &mut PrevPosition,
             &mut Velocity,
             &ProjectileSpawner,
+            &EnemyKind,
+            Option<&mut Diving>,
         )>()
         .with::<&Enemy>()
     {
+        // Divers fire on their own, their slot still marches with the formation
+        let in_formation = diving.is_none();
+        let pos = match diving {
+            Some(diving) => &mut diving.slot,
+            None => pos,
+        };
+
         match game_state.enemy_direction {
             Direction::Right => {
-                vel.move_accumulator += vel.speed * delta_time.as_secs_f32();
+                vel.move_accumulator += vel.speed * speedup * delta_time.as_secs_f32();
             }
             Direction::Left => {
-                vel.move_accumulator -= vel.speed * delta_time.as_secs_f32();
+                vel.move_accumulator -= vel.speed * speedup * delta_time.as_secs_f32();
             }
             Direction::None => {
                 vel.move_accumulator = 0.0;
@@ -614,58 +1495,66 @@ fn process_enemies(delta_time: Duration, game_state: &mut GameState) {
         }
 
         if vel.move_accumulator >= 1.0 || vel.move_accumulator <= -1.0 {
-            let chance = rand::random::<f64>() * 100.0;
-
-            if proj_spawn.probability > chance {
+            let chance = game_state.rng.random::<f64>() * 100.0;
+
+            if in_formation && proj_spawn.probability > chance {
+                let proj_pos = Position {
+                    x: pos.x + 2,
+                    y: pos.y - 1,
+                };
+                let drift = match kind.stats().fire_pattern {
+                    FirePattern::Straight => None,
+                    FirePattern::Aimed => {
+                        aim_at_nearest(&proj_pos, proj_spawn.projectile_speed.abs(), &cannons)
+                    }
+                };
                 projectiles_to_spawn.push((
-                    Position {
-                        x: pos.x + 2,
-                        y: pos.y - 1,
-                    },
+                    proj_pos,
                     Velocity {
                         move_accumulator: 0.0,
                         speed: proj_spawn.projectile_speed,
                         direction: Direction::None,
                     },
+                    drift,
                 ))
             }
 
             // Move in whole-cell steps, keep fractional remainder to avoid drift and asymmetry
-            let steps = vel.move_accumulator.trunc();
-            let new_pos = pos.x as i32 + steps as i32;
-
-            let old_pos = pos.x;
-            prev_pos.x = old_pos;
-            prev_pos.y = pos.y;
-
-            if new_pos < 3 {
-                pos.x = 2;
-                enemies_hit_wall = true;
-            } else if new_pos > 112 {
-                pos.x = 113;
+            let steps = vel.move_accumulator.trunc() as i32;
+            marched = true;
+
+            // The formation moves as one block, its edge stops at the wall and the
+            // whole block turns around
+            let (min_shift, max_shift) = (
+                PLAYER_MIN_X as i32 - left as i32,
+                PLAYER_MAX_X as i32 - right as i32,
+            );
+            pos.x = (pos.x as i32 + steps.clamp(min_shift, max_shift)) as u16;
+            if steps <= min_shift || steps >= max_shift {
                 enemies_hit_wall = true;
-            } else {
-                pos.x = new_pos as u16;
             }
 
-            vel.move_accumulator -= steps;
+            vel.move_accumulator -= steps as f32;
         }
     }
 
-    for (pos, vel) in projectiles_to_spawn {
-        game_state.world.spawn((
+    for (pos, vel, drift) in projectiles_to_spawn {
+        let projectile = game_state.world.spawn((
             EnemyProjectile,
             pos,
             vel,
-            PrevPosition { x: pos.x, y: pos.y },
             Renderable {
-                sprite_top: "",
-                sprite_bottom: "⣿",
+                sprite_top: Sprite::EMPTY,
+                sprite_bottom: ENEMY_SHOT_SPRITE,
                 width: 1,
-                destroy: false,
-                erased: false,
+                style: ENEMY_PROJECTILE_STYLE,
             },
+            ENEMY_SHOT_BOUNDS,
+            Collides::DestroySelf,
         ));
+        if let Some(drift) = drift {
+            let _ = game_state.world.insert_one(projectile, drift);
+        }
     }
 
     // Switch enemy direction when wall is hit
@@ -676,178 +1565,856 @@ fn process_enemies(delta_time: Duration, game_state: &mut GameState) {
             Direction::None => game_state.enemy_direction = Direction::None,
         }
 
-        for (_id, (pos, prev_pos)) in game_state
+        for (_id, (pos, diving)) in game_state
             .world
-            .query_mut::<(&mut Position, &mut PrevPosition)>()
+            .query_mut::<(&mut Position, Option<&mut Diving>)>()
             .with::<&Enemy>()
         {
-            let old_pos = pos.y;
-            prev_pos.y = old_pos;
-            pos.y = old_pos - 1;
-            if pos.y <= 10 {
-                // Enemies flew too low
-                game_state.game_over_notifier = true;
-            }
+            let pos = match diving {
+                Some(diving) => &mut diving.slot,
+                None => pos,
+            };
+            pos.y -= 1;
+        }
+
+        // Only the lowest row still standing decides whether the invasion landed
+        if bottom - 1 <= INVASION_ROW {
+            game_state.game_over_notifier = true;
         }
     }
-}
 
-fn process_enemy_projectiles(
-    delta_time: Duration,
-    game_state: &mut GameState,
-) -> Result<(), Box<dyn Error>> {
-    let mut projectiles_to_erase: Vec<Entity> = Vec::new();
+    // The formation switches frames every few cells it marches and on every step down
+    if marched {
+        game_state.formation_cells += 1;
+    }
+    if game_state.formation_cells >= FORMATION_FRAME_CELLS || enemies_hit_wall {
+        game_state.formation_cells = 0;
+        game_state.formation_frame = (game_state.formation_frame + 1) % 2;
 
-    for (id, (pos, prev_pos, vel, renderable)) in game_state
-        .world
-        .query_mut::<(
-            &mut Position,
-            &mut PrevPosition,
-            &mut Velocity,
-            &mut Renderable,
-        )>()
-        .with::<&EnemyProjectile>()
-    {
-        // projectile sprite was destroyed by renderer
-        if renderable.erased {
-            projectiles_to_erase.push(id);
+        let frame = game_state.formation_frame;
+        for (_id, (renderable, kind)) in game_state
+            .world
+            .query_mut::<(&mut Renderable, &EnemyKind)>()
+            .with::<&Enemy>()
+        {
+            (renderable.sprite_top, renderable.sprite_bottom) = kind.stats().frames[frame];
         }
+    }
+}
 
-        vel.move_accumulator += vel.speed * delta_time.as_secs_f32();
+/// Sends invaders from the formation on dives and flies the ones already diving
+fn process_divers(delta_time: Duration, game_state: &mut GameState) {
+    let dt = delta_time.as_secs_f32();
+    let cannons: Vec<Position> = game_state
+        .world
+        .query_mut::<&Position>()
+        .with::<hecs::Or<&Player, &CoPlayer>>()
+        .into_iter()
+        .map(|(_, pos)| *pos)
+        .collect();
 
-        if vel.move_accumulator >= 1.0 || vel.move_accumulator <= -1.0 {
-            // Move in whole-cell steps, keep fractional remainder to avoid drift and asymmetry
-            let steps = vel.move_accumulator.trunc();
-            let new_pos = pos.y as i32 + steps as i32;
+    let diving_count = game_state
+        .world
+        .query_mut::<()>()
+        .with::<&Diving>()
+        .into_iter()
+        .count();
 
-            let old_pos = pos.y;
-            prev_pos.y = old_pos;
+    if diving_count < game_state.max_divers as usize {
+        game_state.dive_timer -= dt;
+    }
+    if game_state.dive_timer <= 0.0 && !cannons.is_empty() {
+        game_state.dive_timer = game_state
+            .rng
+            .random_range(DIVE_MIN_INTERVAL..DIVE_MAX_INTERVAL);
+        start_dive(game_state, &cannons);
+    }
 
-            if new_pos < 6 || new_pos > 39 {
-                renderable.destroy = true;
-            } else {
-                pos.y = new_pos as u16;
-            }
+    let mut shots: Vec<Position> = Vec::new();
+    let mut landed: Vec<Entity> = Vec::new();
+
+    for (id, (pos, diving)) in game_state
+        .world
+        .query_mut::<(&mut Position, &mut Diving)>()
+        .with::<&Enemy>()
+    {
+        diving.time += dt;
+        let (x, y, done) = dive_path(diving);
+        pos.x = x.round().clamp(PLAYER_MIN_X as f32, PLAYER_MAX_X as f32) as u16;
+        pos.y = y.round().clamp(6.0, 38.0) as u16;
+
+        diving.fire_timer -= dt;
+        if diving.time < DIVE_DOWN_TIME && diving.fire_timer <= 0.0 {
+            diving.fire_timer = DIVE_FIRE_INTERVAL;
+            shots.push(Position {
+                x: pos.x + 2,
+                y: pos.y - 1,
+            });
+        }
+
+        if done {
+            *pos = diving.slot;
+            landed.push(id);
+        }
+    }
+
+    for id in landed {
+        let _ = game_state.world.remove_one::<Diving>(id);
+    }
+
+    for pos in shots {
+        let drift = aim_at_nearest(&pos, 20.0, &cannons);
+        let projectile = game_state.world.spawn((
+            EnemyProjectile,
+            pos,
+            Velocity {
+                speed: -20.0,
+                move_accumulator: 0.0,
+                direction: Direction::None,
+            },
+            Renderable {
+                sprite_top: Sprite::EMPTY,
+                sprite_bottom: ENEMY_SHOT_SPRITE,
+                width: 1,
+                style: ENEMY_PROJECTILE_STYLE,
+            },
+            ENEMY_SHOT_BOUNDS,
+            Collides::DestroySelf,
+        ));
+        if let Some(drift) = drift {
+            let _ = game_state.world.insert_one(projectile, drift);
+        }
+    }
+}
+
+/// Picks a random invader still in formation and sends it at the closest cannon
+fn start_dive(game_state: &mut GameState, cannons: &[Position]) {
+    let candidates: Vec<(Entity, Position)> = game_state
+        .world
+        .query_mut::<&Position>()
+        .with::<&Enemy>()
+        .without::<&Diving>()
+        .without::<&Despawn>()
+        .into_iter()
+        .map(|(id, pos)| (id, *pos))
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+
+    let (id, slot) = candidates[game_state.rng.random_range(0..candidates.len())];
+    let Some(target) = cannons
+        .iter()
+        .min_by_key(|cannon| cannon.x.abs_diff(slot.x))
+    else {
+        return;
+    };
+    let side = if game_state.rng.random_bool(0.5) {
+        -1.0
+    } else {
+        1.0
+    };
+    let loop_around = game_state.rng.random_bool(DIVE_LOOP_CHANCE);
+
+    let _ = game_state.world.insert_one(
+        id,
+        Diving {
+            slot,
+            start_x: slot.x as f32,
+            start_y: slot.y as f32,
+            target_x: target.x as f32,
+            side,
+            loop_around,
+            time: 0.0,
+            fire_timer: DIVE_FIRE_INTERVAL / 2.0,
+        },
+    );
+}
+
+/// Point on a cubic Bezier curve at `t` in 0..=1
+fn bezier(p: [(f32, f32); 4], t: f32) -> (f32, f32) {
+    let u = 1.0 - t;
+    let weights = [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t];
+    let mut point = (0.0, 0.0);
+    for ((x, y), weight) in p.into_iter().zip(weights) {
+        point.0 += x * weight;
+        point.1 += y * weight;
+    }
+    point
+}
+
+/// Position of a diver along its path and whether it is back in its slot
+fn dive_path(diving: &Diving) -> (f32, f32, bool) {
+    let start = (diving.start_x, diving.start_y);
+    let slot = (diving.slot.x as f32, diving.slot.y as f32);
+    let side = diving.side;
+
+    // Swing up and out of the formation, then curve down at the cannon
+    if diving.time < DIVE_DOWN_TIME {
+        let bottom_y = if diving.loop_around { 6.0 } else { DIVE_BOTTOM };
+        let (x, y) = bezier(
+            [
+                start,
+                (start.0 + side * 20.0, start.1 + 6.0),
+                (diving.target_x - side * 30.0, bottom_y + 8.0),
+                (diving.target_x, bottom_y),
+            ],
+            diving.time / DIVE_DOWN_TIME,
+        );
+        return (x, y, false);
+    }
+
+    let time = diving.time - DIVE_DOWN_TIME;
+    if !diving.loop_around {
+        // Turn back up into the slot, which kept moving with the formation
+        let t = (time / DIVE_BACK_TIME).min(1.0);
+        let (x, y) = bezier(
+            [
+                (diving.target_x, DIVE_BOTTOM),
+                (diving.target_x + side * 20.0, DIVE_BOTTOM - 4.0),
+                (slot.0, slot.1 - 12.0),
+                slot,
+            ],
+            t,
+        );
+        return (x, y, t >= 1.0);
+    }
+
+    // Fall out through the bottom and come back in from the top
+    if time < DIVE_EXIT_TIME {
+        return (diving.target_x, 6.0 - 10.0 * time, false);
+    }
+    let t = ((time - DIVE_EXIT_TIME) / DIVE_BACK_TIME).min(1.0);
+    let x = diving.target_x + (slot.0 - diving.target_x) * t;
+    let y = 38.0 + (slot.1 - 38.0) * t;
+    (x, y, t >= 1.0)
+}
+
+/// Sideways drift that steers a shot falling at `fall_speed` towards the closest cannon
+fn aim_at_nearest(from: &Position, fall_speed: f32, cannons: &[Position]) -> Option<Drift> {
+    let target = cannons
+        .iter()
+        .min_by_key(|cannon| cannon.x.abs_diff(from.x))?;
+
+    // Aim at the middle of the 5 cell wide cannon
+    let dx = (target.x + 2) as f32 - from.x as f32;
+    let dy = from.y.saturating_sub(target.y).max(1) as f32;
+    let slope = (dx / dy).clamp(-AIMED_SHOT_MAX_SLOPE, AIMED_SHOT_MAX_SLOPE);
+
+    Some(Drift {
+        speed: slope * fall_speed,
+        move_accumulator: 0.0,
+    })
+}
+
+/// Moves a projectile sideways, returns false once it leaves the play field
+fn apply_drift(delta_time: Duration, pos: &mut Position, drift: &mut Drift) -> bool {
+    drift.move_accumulator += drift.speed * delta_time.as_secs_f32();
+
+    if drift.move_accumulator >= 1.0 || drift.move_accumulator <= -1.0 {
+        let steps = drift.move_accumulator.trunc();
+        let new_pos = pos.x as i32 + steps as i32;
+
+        if !(FIELD_MARGIN as i32..(SCREEN_WIDTH - FIELD_MARGIN) as i32).contains(&new_pos) {
+            return false;
+        }
+        pos.x = new_pos as u16;
+
+        drift.move_accumulator -= steps;
+    }
+
+    true
+}
+
+/// Lets power-up drops fall until they are caught or hit the ground
+fn process_power_ups(delta_time: Duration, game_state: &mut GameState) {
+    let mut drops_out: Vec<Entity> = Vec::new();
+
+    for (id, (pos, vel)) in game_state
+        .world
+        .query_mut::<(&mut Position, &mut Velocity)>()
+        .with::<&PowerUp>()
+    {
+        vel.move_accumulator += vel.speed * delta_time.as_secs_f32();
+
+        if vel.move_accumulator >= 1.0 || vel.move_accumulator <= -1.0 {
+            let steps = vel.move_accumulator.trunc();
+            let new_pos = pos.y as i32 + steps as i32;
+
+            if new_pos < 6 {
+                drops_out.push(id);
+            } else {
+                pos.y = new_pos as u16;
+            }
 
             vel.move_accumulator -= steps;
         }
     }
 
-    for proj in projectiles_to_erase {
-        game_state.world.despawn(proj)?;
+    for drop in drops_out {
+        let _ = game_state.world.insert_one(drop, Despawn);
     }
 
-    Ok(())
+    // Timed effects run out on both cannons
+    for (_, power_ups) in game_state.world.query_mut::<&mut PowerUps>() {
+        let dt = delta_time.as_secs_f32();
+        power_ups.rapid_fire = (power_ups.rapid_fire - dt).max(0.0);
+        power_ups.spread_shot = (power_ups.spread_shot - dt).max(0.0);
+        power_ups.piercing = (power_ups.piercing - dt).max(0.0);
+        power_ups.shield = (power_ups.shield - dt).max(0.0);
+        power_ups.fire_cooldown = (power_ups.fire_cooldown - dt).max(0.0);
+    }
 }
 
-fn entity_cleanup(world: &mut World) -> Result<(), Box<dyn Error>> {
-    let mut entities_erased: Vec<Entity> = Vec::new();
+fn process_enemy_projectiles(
+    delta_time: Duration,
+    game_state: &mut GameState,
+) -> Result<(), Box<dyn Error>> {
+    let mut projectiles_out: Vec<Entity> = Vec::new();
+
+    for (id, (pos, vel, drift)) in game_state
+        .world
+        .query_mut::<(&mut Position, &mut Velocity, Option<&mut Drift>)>()
+        .with::<&EnemyProjectile>()
+    {
+        if let Some(drift) = drift
+            && !apply_drift(delta_time, pos, drift)
+        {
+            projectiles_out.push(id);
+            continue;
+        }
+
+        vel.move_accumulator += vel.speed * delta_time.as_secs_f32();
+
+        if vel.move_accumulator >= 1.0 || vel.move_accumulator <= -1.0 {
+            // Move in whole-cell steps, keep fractional remainder to avoid drift and asymmetry
+            let steps = vel.move_accumulator.trunc();
+            let new_pos = pos.y as i32 + steps as i32;
+
+            if !(6..=FIELD_TOP as i32).contains(&new_pos) {
+                projectiles_out.push(id);
+            } else {
+                pos.y = new_pos as u16;
+            }
 
-    for (id, renderable) in world.query_mut::<&Renderable>() {
-        if renderable.erased {
-            entities_erased.push(id);
+            vel.move_accumulator -= steps;
         }
     }
 
-    for entity_id in entities_erased {
-        world.despawn(entity_id)?;
+    for proj in projectiles_out {
+        game_state.world.insert_one(proj, Despawn)?;
     }
 
     Ok(())
 }
 
-// TODO: Collision system is quite bad:
-// Instead it should detect any objects with Positions and we need a new
-// property like 'Collides'. We should create a map of collisions that occured
-// with defined behaviors. Collides could be an enum struct with Collides::LoseHP or
-// Collides::DestroySelf etc
-fn enemy_collision_detection(game_state: &mut GameState) {
-    let mut entities_hit: Vec<Entity> = Vec::new();
-    let mut need_new_enemies = false;
+/// Spawns the mystery UFO at random intervals and flies it across the top row
+fn process_ufo(delta_time: Duration, game_state: &mut GameState) {
+    let ufo_exists = game_state
+        .world
+        .query_mut::<()>()
+        .with::<&Ufo>()
+        .into_iter()
+        .next()
+        .is_some();
+
+    // The boss health bar takes the top row
+    if !ufo_exists && !game_state.boss_wave {
+        game_state.ufo_timer -= delta_time.as_secs_f32();
+        if game_state.ufo_timer > 0.0 {
+            return;
+        }
+        game_state.ufo_timer = game_state
+            .rng
+            .random_range(UFO_MIN_INTERVAL..UFO_MAX_INTERVAL);
+
+        let (x, direction) = if game_state.rng.random_bool(0.5) {
+            (2, Direction::Right)
+        } else {
+            (SCREEN_WIDTH - FIELD_MARGIN - UFO_WIDTH, Direction::Left)
+        };
+        let points = UFO_POINTS[game_state.rng.random_range(0..UFO_POINTS.len())];
+
+        game_state.world.spawn((
+            Ufo { points },
+            Position { x, y: UFO_Y },
+            Velocity {
+                speed: game_state.rng.random_range(15.0..30.0),
+                move_accumulator: 0.0,
+                direction,
+            },
+            Renderable {
+                sprite_top: UFO_SPRITE,
+                sprite_bottom: Sprite::EMPTY,
+                width: UFO_WIDTH,
+                style: UFO_STYLE,
+            },
+            BoundingBox {
+                width: UFO_WIDTH,
+                height: 1,
+                y_offset: 0,
+                layer: LAYER_UFO,
+                mask: LAYER_PLAYER_SHOT,
+            },
+            Collides::DestroySelf,
+        ));
+        return;
+    }
 
+    let mut ufo_out: Vec<Entity> = Vec::new();
+    for (id, (pos, vel)) in game_state
+        .world
+        .query_mut::<(&mut Position, &mut Velocity)>()
+        .with::<&Ufo>()
     {
-        let projectile_data = game_state
-            .world
-            .query::<&Position>()
-            .with::<&PlayerProjectile>()
-            .iter()
-            .map(|(id, pos)| (id, *pos))
-            .next();
+        match vel.direction {
+            Direction::Right => vel.move_accumulator += vel.speed * delta_time.as_secs_f32(),
+            Direction::Left => vel.move_accumulator -= vel.speed * delta_time.as_secs_f32(),
+            Direction::None => vel.move_accumulator = 0.0,
+        }
 
-        if let Some((proj_id, proj_pos)) = projectile_data {
-            for (enemy_id, (enemy_pos, renderable)) in game_state
-                .world
-                .query_mut::<(&Position, &Renderable)>()
-                .with::<&Enemy>()
+        if vel.move_accumulator >= 1.0 || vel.move_accumulator <= -1.0 {
+            // Move in whole-cell steps, keep fractional remainder to avoid drift and asymmetry
+            let steps = vel.move_accumulator.trunc();
+            let new_pos = pos.x as i32 + steps as i32;
+
+            // Leaves once it passes the opposite wall
+            if new_pos < FIELD_MARGIN as i32
+                || new_pos + UFO_WIDTH as i32 > (SCREEN_WIDTH - FIELD_MARGIN) as i32
             {
-                if proj_pos.x >= enemy_pos.x
-                    && proj_pos.x <= enemy_pos.x + renderable.width
-                    && proj_pos.y == enemy_pos.y
-                {
-                    entities_hit.push(proj_id);
-                    entities_hit.push(enemy_id);
-                    game_state.score += 10;
-                    game_state.score_updated = true;
-                    if game_state.enemy_amount == 1 {
-                        game_state.enemy_speed_multiplier *= 1.2;
-                        game_state.enemy_proj_prob_multiplier *= 3.0;
-                        game_state.enemy_amount = 30;
-                        need_new_enemies = true;
-                    } else {
-                        game_state.enemy_amount -= 1;
-                    }
+                ufo_out.push(id);
+            } else {
+                pos.x = new_pos as u16;
+            }
+
+            vel.move_accumulator -= steps;
+        }
+    }
+
+    for ufo in ufo_out {
+        let _ = game_state.world.insert_one(ufo, Despawn);
+    }
+}
+
+fn update_floating_texts(delta_time: Duration, game_state: &mut GameState) {
+    let mut texts_expired: Vec<Entity> = Vec::new();
+
+    for (id, text) in game_state.world.query_mut::<&mut FloatingText>() {
+        text.ttl -= delta_time.as_secs_f32();
+        if text.ttl <= 0.0 {
+            texts_expired.push(id);
+        }
+    }
+
+    for text in texts_expired {
+        let _ = game_state.world.insert_one(text, Despawn);
+    }
+}
+
+fn entity_cleanup(game_state: &mut GameState) -> Result<(), Box<dyn Error>> {
+    let mut entities_despawned: Vec<Entity> = Vec::new();
+
+    for (id, ()) in game_state.world.query_mut::<()>().with::<&Despawn>() {
+        entities_despawned.push(id);
+    }
+
+    for entity_id in entities_despawned {
+        game_state.world.despawn(entity_id)?;
+    }
+
+    // A spread can have several shots in flight, the cannon reloads once all are gone
+    let mut player_projectile_exists = false;
+    let mut coplayer_projectile_exists = false;
+    for (_, coplayer_projectile) in game_state
+        .world
+        .query_mut::<Option<&CoPlayerProjectile>>()
+        .with::<&PlayerProjectile>()
+    {
+        if coplayer_projectile.is_some() {
+            coplayer_projectile_exists = true;
+        } else {
+            player_projectile_exists = true;
+        }
+    }
+    game_state.player_projectile_exists = player_projectile_exists;
+    game_state.coplayer_handler.projectile_exists = coplayer_projectile_exists;
+
+    Ok(())
+}
+
+/// Resolves every overlapping pair of colliders, applies their `Collides` reaction
+/// and records a `CollisionEvent` for the systems reacting to hits
+fn collision_detection(game_state: &mut GameState) {
+    game_state.collision_events.clear();
+
+    let colliders: Vec<(Entity, Position, BoundingBox)> = game_state
+        .world
+        .query::<(&Position, &BoundingBox)>()
+        .with::<&Collides>()
+        .without::<&Despawn>()
+        .iter()
+        .map(|(id, (pos, bounds))| (id, *pos, *bounds))
+        .collect();
+
+    let mut destroyed: Vec<Entity> = Vec::new();
+
+    for (i, (a, a_pos, a_bounds)) in colliders.iter().enumerate() {
+        for (b, b_pos, b_bounds) in &colliders[i + 1..] {
+            let a_reacts = a_bounds.mask & b_bounds.layer != 0;
+            let b_reacts = b_bounds.mask & a_bounds.layer != 0;
+            if !(a_reacts || b_reacts) || !a_bounds.overlaps(a_pos, b_bounds, b_pos) {
+                continue;
+            }
+
+            // An entity destroyed earlier this tick can't hit anything else,
+            // e.g. a shot absorbed by a bunker won't reach the invader behind it
+            if destroyed.contains(a) || destroyed.contains(b) {
+                continue;
+            }
+            if pierced(&game_state.world, *a, *b) || pierced(&game_state.world, *b, *a) {
+                continue;
+            }
+
+            for (entity, other, reacts) in [(*a, *b, a_reacts), (*b, *a, b_reacts)] {
+                if !reacts {
+                    continue;
+                }
+                let is_destroyed = apply_collision(&mut game_state.world, entity, other);
+                if is_destroyed {
+                    destroyed.push(entity);
+                }
+                game_state.collision_events.push(CollisionEvent {
+                    entity,
+                    other,
+                    destroyed: is_destroyed,
+                });
+                if let Ok(mut pierced) = game_state.world.get::<&mut Pierced>(other) {
+                    pierced.targets.push(entity);
                 }
             }
         }
     }
 
-    if need_new_enemies {
-        spawn_enemies(
-            game_state.enemy_proj_prob_multiplier,
-            game_state.enemy_speed_multiplier,
-            &mut game_state.world,
-        );
+    for entity_id in destroyed {
+        let _ = game_state.world.insert_one(entity_id, Despawn);
+    }
+}
+
+/// Whether `shot` is a piercing shot that already went through `target`
+fn pierced(world: &World, shot: Entity, target: Entity) -> bool {
+    world
+        .get::<&Pierced>(shot)
+        .is_ok_and(|pierced| pierced.targets.contains(&target))
+}
+
+/// Applies the `Collides` reaction of an entity, returns whether it is destroyed
+fn apply_collision(world: &mut World, entity: Entity, other: Entity) -> bool {
+    let Ok(collides) = world.get::<&Collides>(entity).map(|collides| *collides) else {
+        return false;
+    };
+
+    // An invader ramming a cannon is destroyed outright, so the contact costs a
+    // single life instead of one per tick it overlaps
+    if world.satisfies::<&Enemy>(entity).unwrap_or(false)
+        && world
+            .satisfies::<hecs::Or<&Player, &CoPlayer>>(other)
+            .unwrap_or(false)
+    {
+        return true;
     }
 
-    for entity_id in entities_hit {
-        if let Ok(mut renderable) = game_state.world.get::<&mut Renderable>(entity_id) {
-            renderable.destroy = true;
+    match collides {
+        Collides::DestroySelf => true,
+        Collides::LoseHP => match world.get::<&mut Health>(entity) {
+            Ok(mut health) => {
+                health.hp = health.hp.saturating_sub(1);
+                health.hp == 0
+            }
+            Err(_) => true,
+        },
+        Collides::LoseLife | Collides::Pierce => false,
+    }
+}
+
+/// Plays explosions where invaders died and where two shots met
+fn spawn_explosions(game_state: &mut GameState) {
+    let mut explosions: Vec<(EffectKind, Position)> = Vec::new();
+
+    for event in &game_state.collision_events {
+        if !event.destroyed {
+            continue;
+        }
+        let Ok(pos) = game_state.world.get::<&Position>(event.entity) else {
+            continue;
+        };
+
+        if game_state
+            .world
+            .satisfies::<&Enemy>(event.entity)
+            .unwrap_or(false)
+        {
+            explosions.push((EffectKind::EnemyExplosion, *pos));
+        } else if game_state
+            .world
+            .satisfies::<&EnemyProjectile>(event.entity)
+            .unwrap_or(false)
+            && game_state
+                .world
+                .satisfies::<&PlayerProjectile>(event.other)
+                .unwrap_or(false)
+        {
+            // Enemy shots are drawn one row below their position
+            explosions.push((
+                EffectKind::ShotBurst,
+                Position {
+                    x: pos.x,
+                    y: pos.y - 1,
+                },
+            ));
         }
     }
+
+    for (kind, pos) in explosions {
+        spawn_effect(&mut game_state.world, kind, pos);
+    }
 }
 
-fn player_collision_detection(game_state: &mut GameState) {
-    let mut player_hit = false;
+fn spawn_effect(world: &mut World, kind: EffectKind, pos: Position) {
+    let (sprite_top, sprite_bottom) = kind.frames()[0];
+    world.spawn((
+        Effect { kind, elapsed: 0.0 },
+        pos,
+        Renderable {
+            sprite_top,
+            sprite_bottom,
+            width: kind.width(),
+            style: kind.style(),
+        },
+    ));
+}
+
+/// Advances effect animations and despawns the ones that played their last frame
+fn update_effects(delta_time: Duration, game_state: &mut GameState) {
+    let mut effects_done: Vec<Entity> = Vec::new();
 
-    let player_data = game_state
+    for (id, (effect, renderable)) in game_state
         .world
-        .query::<&Position>()
-        .with::<&Player>()
+        .query_mut::<(&mut Effect, &mut Renderable)>()
+    {
+        effect.elapsed += delta_time.as_secs_f32();
+        let frame = (effect.elapsed / effect.kind.frame_time()) as usize;
+        match effect.kind.frames().get(frame) {
+            Some(&(sprite_top, sprite_bottom)) => {
+                renderable.sprite_top = sprite_top;
+                renderable.sprite_bottom = sprite_bottom;
+            }
+            None => effects_done.push(id),
+        }
+    }
+
+    for effect in effects_done {
+        let _ = game_state.world.insert_one(effect, Despawn);
+    }
+}
+
+/// Thins out the glyph of bunker cells that were hit
+fn erode_bunkers(game_state: &mut GameState) {
+    let cells_hit: Vec<Entity> = game_state
+        .collision_events
         .iter()
-        .map(|(id, pos)| (id, *pos))
-        .next();
+        .map(|event| event.entity)
+        .filter(|&entity| {
+            game_state
+                .world
+                .satisfies::<&BunkerCell>(entity)
+                .unwrap_or(false)
+        })
+        .collect();
 
-    if let Some((_, player_pos)) = player_data {
-        for (_, (proj_pos, renderable)) in game_state
+    for cell_id in cells_hit {
+        if let Ok((health, renderable)) = game_state
             .world
-            .query_mut::<(&Position, &mut Renderable)>()
-            .with::<&EnemyProjectile>()
+            .query_one_mut::<(&Health, &mut Renderable)>(cell_id)
         {
-            if proj_pos.x >= player_pos.x
-                && proj_pos.x <= player_pos.x + 5
-                && player_pos.y == proj_pos.y - 1
-            {
-                player_hit = true;
-                renderable.destroy = true;
+            renderable.sprite_top = bunker_sprite(health.hp);
+        }
+    }
+}
+
+/// Awards points for invaders and saucers shot down and announces the next wave
+/// once the last invader is gone, whether it was shot or rammed a cannon
+fn score_kills(game_state: &mut GameState) {
+    let mut popups: Vec<(Position, i32)> = Vec::new();
+    let mut drops: Vec<Position> = Vec::new();
+
+    for event in &game_state.collision_events {
+        if !event.destroyed {
+            continue;
+        }
+
+        // Co-player shots carry `PlayerProjectile` as well
+        let shot_down = game_state
+            .world
+            .satisfies::<&PlayerProjectile>(event.other)
+            .unwrap_or(false);
+
+        if let Ok(kind) = game_state.world.get::<&EnemyKind>(event.entity) {
+            if shot_down {
+                game_state.score += kind.stats().points;
+                if game_state.rng.random_bool(POWER_UP_DROP_CHANCE)
+                    && let Ok(pos) = game_state.world.get::<&Position>(event.entity)
+                {
+                    drops.push(Position {
+                        x: pos.x + 2,
+                        y: pos.y - 1,
+                    });
+                }
+            }
+            game_state.enemy_amount = game_state.enemy_amount.saturating_sub(1);
+            if game_state.enemy_amount == 0 {
+                if game_state.waves.boss_after(game_state.wave) {
+                    game_state.boss_wave = true;
+                } else {
+                    game_state.wave += 1;
+                }
+                game_state.wave_banner_timer = WAVE_BANNER_TIME;
+            }
+        } else if shot_down && let Ok(ufo) = game_state.world.get::<&Ufo>(event.entity) {
+            game_state.score += ufo.points;
+            if let Ok(pos) = game_state.world.get::<&Position>(event.entity) {
+                popups.push((*pos, ufo.points));
+            }
+        }
+    }
+
+    for pos in drops {
+        let kind = POWER_UP_TABLE[game_state.rng.random_range(0..POWER_UP_TABLE.len())];
+        game_state.world.spawn((
+            PowerUp { kind },
+            pos,
+            Velocity {
+                speed: POWER_UP_FALL_SPEED,
+                move_accumulator: 0.0,
+                direction: Direction::None,
+            },
+            Renderable {
+                sprite_top: kind.sprite(),
+                sprite_bottom: Sprite::EMPTY,
+                width: 1,
+                style: kind.style(),
+            },
+            POWER_UP_BOUNDS,
+            Collides::DestroySelf,
+        ));
+    }
+
+    for (pos, points) in popups {
+        game_state.world.spawn((
+            pos,
+            FloatingText {
+                text: points.to_string(),
+                ttl: UFO_SCORE_TTL,
+                style: UFO_SCORE_STYLE,
+            },
+        ));
+    }
+}
+
+/// Applies power-ups caught by the player or co-player
+fn collect_power_ups(game_state: &mut GameState) {
+    for event in &game_state.collision_events {
+        let Ok(kind) = game_state
+            .world
+            .get::<&PowerUp>(event.entity)
+            .map(|power_up| power_up.kind)
+        else {
+            continue;
+        };
+        let Ok(mut power_ups) = game_state.world.get::<&mut PowerUps>(event.other) else {
+            continue;
+        };
+
+        match kind {
+            PowerUpKind::RapidFire => power_ups.rapid_fire = kind.duration(),
+            PowerUpKind::SpreadShot => power_ups.spread_shot = kind.duration(),
+            PowerUpKind::Piercing => power_ups.piercing = kind.duration(),
+            PowerUpKind::Shield => power_ups.shield = kind.duration(),
+            PowerUpKind::ExtraLife => {
+                game_state.player_lives = (game_state.player_lives + 1).min(MAX_PLAYER_LIVES);
             }
         }
     }
+}
+
+/// Takes a shared life when the player or co-player is hit, at most one per tick.
+/// A shielded cannon only loses the shot.
+fn damage_players(game_state: &mut GameState) {
+    let mut players_hit = false;
+    let mut explosions: Vec<Position> = Vec::new();
+
+    for event in &game_state.collision_events {
+        let shielded = game_state
+            .world
+            .get::<&PowerUps>(event.entity)
+            .is_ok_and(|power_ups| power_ups.shield > 0.0);
+        if shielded {
+            continue;
+        }
+
+        let is_coplayer = game_state
+            .world
+            .satisfies::<&CoPlayer>(event.entity)
+            .unwrap_or(false);
+        if event.entity == game_state.player_entity {
+            game_state.player_hit_timer = PLAYER_HIT_FLASH;
+        } else if !is_coplayer {
+            continue;
+        }
+
+        players_hit = true;
+        if let Ok(pos) = game_state.world.get::<&Position>(event.entity) {
+            explosions.push(*pos);
+        }
+    }
+
+    for pos in explosions {
+        spawn_effect(&mut game_state.world, EffectKind::PlayerExplosion, pos);
+    }
 
-    if player_hit {
-        game_state.player_lives -= 1;
-        game_state.score_updated = true;
+    if players_hit {
+        game_state.player_lives = game_state.player_lives.saturating_sub(1);
         if game_state.player_lives == 0 {
             game_state.game_over_notifier = true;
         }
     }
 }
+
+/// Tints the player red for a short moment after a hit
+fn update_player_flash(delta_time: Duration, game_state: &mut GameState) {
+    game_state.player_hit_timer = (game_state.player_hit_timer - delta_time.as_secs_f32()).max(0.0);
+
+    if let Ok((renderable, power_ups)) = game_state
+        .world
+        .query_one_mut::<(&mut Renderable, &PowerUps)>(game_state.player_entity)
+    {
+        renderable.style = if game_state.player_hit_timer > 0.0 {
+            PLAYER_HIT_STYLE
+        } else if power_ups.shield > 0.0 {
+            PLAYER_SHIELD_STYLE
+        } else {
+            PLAYER_STYLE
+        };
+    }
+
+    for (_, (renderable, power_ups)) in game_state
+        .world
+        .query_mut::<(&mut Renderable, &PowerUps)>()
+        .with::<&CoPlayer>()
+    {
+        renderable.style = if power_ups.shield > 0.0 {
+            COPLAYER_SHIELD_STYLE
+        } else {
+            COPLAYER_STYLE
+        };
+    }
+}
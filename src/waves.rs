@@ -0,0 +1,279 @@
+use crate::EnemyKind;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Waves shipped with the game, used when no waves file is given or it fails to load
+const BUILTIN_WAVES: &str = include_str!("../assets/waves.toml");
+
+/// Widest formation that still fits between the walls
+pub const MAX_FORMATION_COLUMNS: usize = 15;
+/// Deepest formation that still starts above the bunkers
+pub const MAX_FORMATION_ROWS: usize = 5;
+/// Speed-up used when a waves file has none, the formation keeps its speed
+const DEFAULT_SPEEDUP: [(f32, f32); 1] = [(1.0, 1.0)];
+
+/// Things that happen when a wave starts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WaveEvent {
+    /// Sends the mystery UFO right away
+    Ufo,
+    /// Restores every bunker to full health
+    RebuildBunkers,
+}
+
+/// Validated wave, ready to be spawned
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wave {
+    /// Rows of the formation from the top down, `None` leaves a slot empty
+    pub formation: Vec<Vec<Option<EnemyKind>>>,
+    pub speed: f32,
+    pub fire_rate: f32,
+    /// How many invaders may break formation and dive at once
+    pub divers: u8,
+    pub events: Vec<WaveEvent>,
+}
+
+impl Wave {
+    pub fn enemy_count(&self) -> u16 {
+        self.formation.iter().flatten().flatten().count() as u16
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct WaveSet {
+    pub waves: Vec<Wave>,
+    /// Speed factor applied for every repeat of the last wave
+    pub speed_scale: f32,
+    /// Fire rate factor applied for every repeat of the last wave
+    pub fire_scale: f32,
+    /// A boss appears after every wave with a number divisible by this, 0 disables bosses
+    pub boss_every: u32,
+    pub boss_hp: u16,
+    /// `(share of invaders left, speed factor)` points, from the full formation down
+    pub speedup: Vec<(f32, f32)>,
+}
+
+impl WaveSet {
+    /// Whether clearing the given wave is followed by a boss fight
+    pub fn boss_after(&self, number: u32) -> bool {
+        self.boss_every > 0 && number.is_multiple_of(self.boss_every)
+    }
+
+    /// Formation speed factor with the given share of invaders left, interpolated
+    /// between the points of the speed-up curve
+    pub fn speedup_at(&self, remaining: f32) -> f32 {
+        let Some(&(mut share, mut factor)) = self.speedup.first() else {
+            return 1.0;
+        };
+        if remaining >= share {
+            return factor;
+        }
+
+        for &(next_share, next_factor) in &self.speedup[1..] {
+            if remaining >= next_share {
+                let t = (share - remaining) / (share - next_share);
+                return factor + (next_factor - factor) * t;
+            }
+            (share, factor) = (next_share, next_factor);
+        }
+        factor
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WaveFile {
+    #[serde(default = "default_speed_scale")]
+    speed_scale: f32,
+    #[serde(default = "default_fire_scale")]
+    fire_scale: f32,
+    #[serde(default)]
+    boss_every: u32,
+    #[serde(default = "default_boss_hp")]
+    boss_hp: u16,
+    #[serde(default = "default_speedup")]
+    speedup: Vec<(f32, f32)>,
+    #[serde(default, rename = "wave")]
+    waves: Vec<WaveEntry>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WaveEntry {
+    formation: Vec<String>,
+    #[serde(default = "default_multiplier")]
+    speed: f32,
+    #[serde(default = "default_multiplier")]
+    fire_rate: f32,
+    #[serde(default)]
+    divers: u8,
+    #[serde(default)]
+    events: Vec<WaveEvent>,
+}
+
+fn default_speed_scale() -> f32 {
+    1.2
+}
+
+fn default_fire_scale() -> f32 {
+    3.0
+}
+
+fn default_boss_hp() -> u16 {
+    20
+}
+
+fn default_speedup() -> Vec<(f32, f32)> {
+    DEFAULT_SPEEDUP.to_vec()
+}
+
+fn default_multiplier() -> f32 {
+    1.0
+}
+
+impl WaveSet {
+    pub fn builtin() -> Self {
+        Self::parse(BUILTIN_WAVES).expect("built-in waves are valid")
+    }
+
+    /// Reads and validates a waves file, returning every problem found
+    pub fn load(path: &Path) -> Result<Self, Vec<String>> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| vec![format!("{}: {}", path.display(), e)])?;
+        Self::parse(&text)
+    }
+
+    pub fn parse(text: &str) -> Result<Self, Vec<String>> {
+        let file: WaveFile = toml::from_str(text).map_err(|e| vec![e.message().to_string()])?;
+        let mut errors = Vec::new();
+
+        if file.waves.is_empty() {
+            errors.push("no [[wave]] defined".to_string());
+        }
+        if file.speed_scale <= 0.0 {
+            errors.push("speed_scale must be greater than 0".to_string());
+        }
+        if file.fire_scale <= 0.0 {
+            errors.push("fire_scale must be greater than 0".to_string());
+        }
+        if file.boss_every > 0 && file.boss_hp == 0 {
+            errors.push("boss_hp must be greater than 0".to_string());
+        }
+        if file.speedup.is_empty() {
+            errors.push("speedup needs at least one point".to_string());
+        }
+        for (index, &(share, factor)) in file.speedup.iter().enumerate() {
+            let point = index + 1;
+            if !(0.0..=1.0).contains(&share) {
+                errors.push(format!(
+                    "speedup point {point}: share must be between 0 and 1"
+                ));
+            }
+            if factor <= 0.0 {
+                errors.push(format!(
+                    "speedup point {point}: factor must be greater than 0"
+                ));
+            }
+            if index > 0 && share >= file.speedup[index - 1].0 {
+                errors.push(format!(
+                    "speedup point {point}: shares must go down from the full formation"
+                ));
+            }
+        }
+
+        let mut waves = Vec::new();
+        for (index, entry) in file.waves.into_iter().enumerate() {
+            let number = index + 1;
+
+            if entry.speed <= 0.0 {
+                errors.push(format!("wave {number}: speed must be greater than 0"));
+            }
+            if entry.fire_rate < 0.0 {
+                errors.push(format!("wave {number}: fire_rate must not be negative"));
+            }
+            if entry.formation.len() > MAX_FORMATION_ROWS {
+                errors.push(format!(
+                    "wave {number}: formation has {} rows, at most {MAX_FORMATION_ROWS} fit",
+                    entry.formation.len()
+                ));
+            }
+
+            let mut formation = Vec::new();
+            for (row_index, row) in entry.formation.iter().enumerate() {
+                if row.chars().count() > MAX_FORMATION_COLUMNS {
+                    errors.push(format!(
+                        "wave {number}, row {}: {} columns, at most {MAX_FORMATION_COLUMNS} fit",
+                        row_index + 1,
+                        row.chars().count()
+                    ));
+                }
+
+                let mut slots = Vec::new();
+                for symbol in row.chars() {
+                    match enemy_from_symbol(symbol) {
+                        Ok(slot) => slots.push(slot),
+                        Err(()) => errors.push(format!(
+                            "wave {number}, row {}: unknown enemy '{symbol}'",
+                            row_index + 1
+                        )),
+                    }
+                }
+                formation.push(slots);
+            }
+
+            let wave = Wave {
+                formation,
+                speed: entry.speed,
+                fire_rate: entry.fire_rate,
+                divers: entry.divers,
+                events: entry.events,
+            };
+            if wave.enemy_count() == 0 {
+                errors.push(format!("wave {number}: formation has no enemies"));
+            }
+            waves.push(wave);
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        Ok(Self {
+            waves,
+            speed_scale: file.speed_scale,
+            fire_scale: file.fire_scale,
+            boss_every: file.boss_every,
+            boss_hp: file.boss_hp,
+            speedup: file.speedup,
+        })
+    }
+
+    /// Wave with the given 1-based number, repeats of the last wave are scaled up
+    pub fn wave(&self, number: u32) -> Wave {
+        self.scaled_wave(number, 1.0)
+    }
+
+    /// The wave with `speed_scale` and `fire_scale` multiplied by `scaling` first,
+    /// as the difficulty presets do
+    pub fn scaled_wave(&self, number: u32, scaling: f32) -> Wave {
+        let last = self.waves.len() - 1;
+        let index = (number.max(1) - 1) as usize;
+        let mut wave = self.waves[index.min(last)].clone();
+
+        let repeats = index.saturating_sub(last) as i32;
+        wave.speed *= (self.speed_scale * scaling).powi(repeats);
+        wave.fire_rate *= (self.fire_scale * scaling).powi(repeats);
+        wave
+    }
+}
+
+fn enemy_from_symbol(symbol: char) -> Result<Option<EnemyKind>, ()> {
+    match symbol {
+        'S' => Ok(Some(EnemyKind::Squid)),
+        'C' => Ok(Some(EnemyKind::Crab)),
+        'O' => Ok(Some(EnemyKind::Octopus)),
+        '.' | ' ' => Ok(None),
+        _ => Err(()),
+    }
+}
@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use invaderse::{DEFAULT_RELEASE_TIMEOUT, FIRST_REPEAT_TIMEOUT, Key, RepeatTracker};
+
+const TIMEOUT: Duration = Duration::from_millis(100);
+
+fn ms(millis: u64) -> Duration {
+    Duration::from_millis(millis)
+}
+
+/// Presses the key like a terminal auto-repeating it from `start`: once, again after
+/// `delay`, then every 30ms for `repeats` more presses. Returns the last press.
+fn hold(
+    tracker: &mut RepeatTracker,
+    key: Key,
+    start: Instant,
+    delay: u64,
+    repeats: u64,
+) -> Instant {
+    assert!(tracker.press(key, start));
+    let mut now = start + ms(delay);
+    assert!(!tracker.press(key, now));
+    for _ in 0..repeats {
+        now += ms(30);
+        assert!(!tracker.press(key, now));
+        assert!(tracker.expire(now).is_empty());
+    }
+    now
+}
+
+#[test]
+fn auto_repeat_keeps_a_key_held() {
+    let mut tracker = RepeatTracker::new(TIMEOUT);
+    let start = Instant::now();
+
+    hold(&mut tracker, Key::Char('a'), start, 300, 9);
+}
+
+#[test]
+fn key_is_released_once_repeats_stop() {
+    let mut tracker = RepeatTracker::new(TIMEOUT);
+    let start = Instant::now();
+    let a = hold(&mut tracker, Key::Char('a'), start, 300, 1);
+    let left = hold(&mut tracker, Key::Left, start + ms(50), 300, 2);
+
+    let released = tracker.expire(a + ms(101));
+    assert_eq!(released, vec![Key::Char('a')]);
+    assert_eq!(tracker.expire(left + ms(101)), vec![Key::Left]);
+
+    // The next press starts a new hold
+    assert!(tracker.press(Key::Char('a'), left + ms(200)));
+}
+
+#[test]
+fn repeat_delay_does_not_release_the_key() {
+    let mut tracker = RepeatTracker::new(DEFAULT_RELEASE_TIMEOUT);
+    let start = Instant::now();
+    tracker.press(Key::Right, start);
+
+    // The first auto-repeat comes long after the press, the later ones quickly
+    assert!(tracker.expire(start + ms(450)).is_empty());
+    assert!(!tracker.press(Key::Right, start + ms(500)));
+    assert!(!tracker.press(Key::Right, start + ms(530)));
+    assert!(tracker.expire(start + ms(600)).is_empty());
+
+    // Once repeating, letting go is noticed within the release timeout
+    assert_eq!(
+        tracker.expire(start + ms(530) + DEFAULT_RELEASE_TIMEOUT * 2),
+        vec![Key::Right]
+    );
+}
+
+#[test]
+fn tap_is_released_after_the_first_repeat_timeout() {
+    let mut tracker = RepeatTracker::new(DEFAULT_RELEASE_TIMEOUT);
+    let start = Instant::now();
+    tracker.press(Key::Char(' '), start);
+
+    assert!(tracker.expire(start + FIRST_REPEAT_TIMEOUT).is_empty());
+    assert_eq!(
+        tracker.expire(start + FIRST_REPEAT_TIMEOUT + ms(1)),
+        vec![Key::Char(' ')]
+    );
+}
+
+#[test]
+fn double_tap_counts_as_two_presses() {
+    let mut tracker = RepeatTracker::new(DEFAULT_RELEASE_TIMEOUT);
+    let start = Instant::now();
+
+    assert!(tracker.press(Key::Down, start));
+    assert!(tracker.press(Key::Down, start + ms(120)));
+    // A third quick tap is still no auto-repeat
+    assert!(tracker.press(Key::Down, start + ms(240)));
+}
+
+#[test]
+fn taps_get_shorter_once_the_repeat_delay_is_learned() {
+    let mut tracker = RepeatTracker::new(DEFAULT_RELEASE_TIMEOUT);
+    let start = Instant::now();
+    let last = hold(&mut tracker, Key::Left, start, 250, 3);
+    assert_eq!(tracker.expire(last + ms(200)), vec![Key::Left]);
+
+    // A single tap now lasts the 250ms repeat delay and a little slack
+    let tap = last + ms(1000);
+    assert!(tracker.press(Key::Left, tap));
+    assert!(tracker.expire(tap + ms(290)).is_empty());
+    assert_eq!(tracker.expire(tap + ms(301)), vec![Key::Left]);
+
+    // A second press before the learned delay is a tap of its own
+    assert!(tracker.press(Key::Right, tap + ms(400)));
+    assert!(tracker.press(Key::Right, tap + ms(580)));
+}
+
+#[test]
+fn short_first_repeat_timeout_shortens_taps() {
+    let mut tracker = RepeatTracker::new(TIMEOUT).with_first_repeat_timeout(TIMEOUT);
+    let start = Instant::now();
+    tracker.press(Key::Char('d'), start);
+
+    assert_eq!(tracker.expire(start + ms(101)), vec![Key::Char('d')]);
+}
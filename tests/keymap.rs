@@ -0,0 +1,98 @@
+use invaderse::{Action, Key, Keymap};
+
+#[test]
+fn defaults_include_arrows_and_space() {
+    let keymap = Keymap::default();
+    assert_eq!(keymap.action(Key::Char('a')), Some(Action::MoveLeft));
+    assert_eq!(keymap.action(Key::Left), Some(Action::MoveLeft));
+    assert_eq!(keymap.action(Key::Right), Some(Action::MoveRight));
+    assert_eq!(keymap.action(Key::Char(' ')), Some(Action::Shoot));
+    assert_eq!(keymap.action(Key::Char('x')), None);
+}
+
+#[test]
+fn parses_several_keys_per_action() {
+    let keymap = Keymap::parse(
+        r#"
+        shoot = ["k", "space", "up"]
+        quit = ["Q"]
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(
+        keymap.keys(Action::Shoot),
+        [Key::Char('k'), Key::Char(' '), Key::Up]
+    );
+    assert_eq!(keymap.keys(Action::Quit), [Key::Char('q')]);
+    // Actions left out keep their defaults
+    assert_eq!(keymap.keys(Action::MoveLeft), [Key::Char('a'), Key::Left]);
+}
+
+#[test]
+fn rejects_conflicting_and_unknown_keys() {
+    let errors = Keymap::parse(
+        r#"
+        move_left = ["a", "f13"]
+        shoot = ["a"]
+        pause = []
+        "#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        errors,
+        vec![
+            "move_left: unknown key 'f13'",
+            "pause: needs at least one key",
+            "a is bound to both move_left and shoot",
+        ]
+    );
+}
+
+#[test]
+fn saved_keymap_loads_back() {
+    let mut keymap = Keymap::default();
+    keymap.toggle(Action::Pause, Key::Enter).unwrap();
+    keymap.toggle(Action::MoveLeft, Key::Char('a')).unwrap();
+
+    assert_eq!(Keymap::parse(&keymap.to_toml()).unwrap(), keymap);
+}
+
+#[test]
+fn rebinding_refuses_taken_and_last_keys() {
+    let mut keymap = Keymap::default();
+
+    assert_eq!(
+        keymap.toggle(Action::Shoot, Key::Char('q')),
+        Err("q is already bound to Quit".to_string())
+    );
+    assert_eq!(
+        keymap.toggle(Action::Quit, Key::Char('q')),
+        Err("q is the only key for Quit".to_string())
+    );
+
+    keymap.toggle(Action::Shoot, Key::Char('w')).unwrap();
+    keymap.toggle(Action::Pause, Key::Char('w')).unwrap();
+    assert_eq!(
+        keymap.reset(Action::Shoot),
+        Err("w is already bound to Pause".to_string())
+    );
+}
+
+#[test]
+fn coop_keys_may_share_keys_with_the_player() {
+    let keymap = Keymap::default();
+    assert_eq!(keymap.action(Key::Left), Some(Action::MoveLeft));
+    assert_eq!(keymap.coop_action(Key::Left), Some(Action::CoopLeft));
+    assert_eq!(keymap.coop_action(Key::Char('a')), None);
+
+    let errors = Keymap::parse(
+        r#"
+        coop_left = ["j"]
+        coop_shoot = ["j"]
+        "#,
+    )
+    .unwrap_err();
+    assert_eq!(errors, vec!["j is bound to both coop_left and coop_shoot"]);
+}
@@ -0,0 +1,293 @@
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+use invaderse::{
+    ENEMY_ROW_STYLES, MIN_COLUMNS, MIN_ROWS, MemoryBackend, MenuEntry, MenuItem, PLAYER_STYLE,
+    Position, Render, SCREEN_HEIGHT, SCREEN_WIDTH, Screen, SpriteSet, Style, create_world,
+    main_menu_left,
+};
+
+/// Smallest terminal the play field is drawn at full size in
+const COLUMNS: u16 = SCREEN_WIDTH + 5;
+const ROWS: u16 = SCREEN_HEIGHT + 5;
+
+/// Compares the frame with `tests/snapshots/<name>.txt`.
+/// Run with `UPDATE_SNAPSHOTS=1` to rewrite the snapshot after an intended change.
+fn assert_snapshot(name: &str, backend: &MemoryBackend) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/snapshots")
+        .join(format!("{}.txt", name));
+    let actual = backend.to_string();
+
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+
+    let expected =
+        fs::read_to_string(&path).unwrap_or_else(|_| panic!("missing snapshot {}", path.display()));
+    assert!(
+        actual == expected,
+        "frame differs from snapshot {}\n--- actual ---\n{}",
+        name,
+        actual
+    );
+}
+
+fn renderer(columns: u16, rows: u16) -> Render<MemoryBackend> {
+    Render::new(MemoryBackend::new(columns, rows)).unwrap()
+}
+
+#[test]
+fn main_menu_frame() {
+    let mut game_state = create_world();
+    game_state.main_menu.active_menu_item = MenuItem::JoinGame;
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render_main_menu(&mut game_state).unwrap();
+
+    assert_snapshot("main_menu", &renderer.backend);
+}
+
+#[test]
+fn settings_frame() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Settings;
+    game_state.settings_menu.selected = 7;
+    game_state.settings_menu.editing = Some("10.0.0.2:40".to_string());
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render_settings_menu(&mut game_state).unwrap();
+
+    assert_snapshot("settings", &renderer.backend);
+}
+
+#[test]
+fn pause_menu_frame() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    game_state.paused = true;
+    game_state.pause_menu.selected = 4;
+    game_state.pause_menu.confirming = true;
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render(&mut game_state).unwrap();
+
+    assert_snapshot("pause_menu", &renderer.backend);
+}
+
+#[test]
+fn controls_frame() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Controls;
+    game_state.controls_menu.selected = 2;
+    game_state.controls_menu.message = Some("q is already bound to Quit".to_string());
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render_controls_menu(&mut game_state).unwrap();
+
+    assert_snapshot("controls", &renderer.backend);
+}
+
+#[test]
+fn first_game_frame() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render(&mut game_state).unwrap();
+
+    assert_snapshot("first_game_frame", &renderer.backend);
+    assert_eq!(renderer.backend.flushes, 1);
+}
+
+#[test]
+fn ascii_sprite_set_frame() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    let mut renderer = renderer(COLUMNS, ROWS);
+    renderer.sprite_set = SpriteSet::Ascii;
+
+    renderer.render(&mut game_state).unwrap();
+
+    assert_snapshot("ascii_sprites", &renderer.backend);
+    assert!(renderer.backend.to_string().is_ascii());
+}
+
+#[test]
+fn game_over_frame() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    game_state.score = 120;
+    game_state.high_score = 80;
+    game_state.end_game();
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render(&mut game_state).unwrap();
+
+    assert_snapshot("game_over", &renderer.backend);
+}
+
+#[test]
+fn unchanged_frame_writes_nothing() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render(&mut game_state).unwrap();
+    let written = renderer.backend.cells_written;
+    renderer.render(&mut game_state).unwrap();
+
+    assert_eq!(renderer.backend.cells_written, written);
+    assert_eq!(renderer.backend.flushes, 2);
+}
+
+#[test]
+fn moved_sprite_only_redraws_changed_cells() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render(&mut game_state).unwrap();
+    let written = renderer.backend.cells_written;
+
+    let player = game_state.player_entity;
+    game_state.world.get::<&mut Position>(player).unwrap().x += 1;
+    renderer.render(&mut game_state).unwrap();
+
+    // Shifting the player by one rewrites the uncovered cell on both rows, the
+    // five cells of the top row and only the newly covered cell of the solid bottom row
+    assert_eq!(renderer.backend.cells_written - written, 8);
+    assert_snapshot("player_moved", &renderer.backend);
+}
+
+#[test]
+fn terminal_too_small() {
+    let mut game_state = create_world();
+    let mut renderer = renderer(MIN_COLUMNS - 1, MIN_ROWS);
+
+    renderer.render_main_menu(&mut game_state).unwrap();
+
+    assert_eq!(renderer.backend.line(0).trim_end(), "Terminal too small");
+    assert!(renderer.backend.lines()[1..].iter().all(|l| l.is_empty()));
+}
+
+#[test]
+fn small_terminal_frame() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    let mut renderer = renderer(MIN_COLUMNS, MIN_ROWS);
+
+    renderer.render(&mut game_state).unwrap();
+
+    assert_snapshot("small_terminal", &renderer.backend);
+}
+
+#[test]
+fn small_terminal_main_menu_frame() {
+    let mut game_state = create_world();
+    let mut renderer = renderer(MIN_COLUMNS, MIN_ROWS);
+
+    renderer.render_main_menu(&mut game_state).unwrap();
+
+    assert_snapshot("small_terminal_main_menu", &renderer.backend);
+    let row = 11;
+    let column = renderer.backend.line(row).find("JoinGame").unwrap() as u16;
+    for x in [column, column + 7] {
+        assert_eq!(
+            renderer.menu_entry_at(&game_state, x, row),
+            Some(MenuEntry::Item(MenuItem::JoinGame))
+        );
+    }
+    assert_eq!(renderer.menu_entry_at(&game_state, column, 12), None);
+}
+
+#[test]
+fn field_scales_up_on_large_terminals() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    let mut renderer = renderer(COLUMNS * 2, ROWS * 2);
+    assert_eq!(renderer.scale(), 2);
+
+    renderer.render(&mut game_state).unwrap();
+
+    // The cannon's top row, at column 2 + 55 and row 42 - 7 of the unscaled frame
+    let cannon = "⣆⣆⡜⡜⣛⣛⢣⢣⣠⣠";
+    for row in [70, 71] {
+        assert_eq!(renderer.backend.line(row).find(cannon), Some(2 * (2 + 55)));
+    }
+    assert!(renderer.backend.line(80).contains("score - 0"));
+    assert_eq!(
+        renderer.backend.line(81).trim(),
+        format!("##{}##", " ".repeat(236))
+    );
+
+    assert_eq!(renderer.field_column(2 * (2 + 40), 2 * (42 - 7)), Some(40));
+}
+
+#[test]
+fn sprites_are_colored() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    let mut renderer = renderer(COLUMNS, ROWS);
+
+    renderer.render(&mut game_state).unwrap();
+
+    // Game bounds start at column 2 and the bottom wall is on row 42
+    let top_enemy = renderer.backend.style(2 + 6, 42 - 38).unwrap();
+    let bottom_enemy = renderer.backend.style(2 + 6, 42 - 30).unwrap();
+    let player = renderer.backend.style(2 + 55, 42 - 7).unwrap();
+    assert_eq!(top_enemy, ENEMY_ROW_STYLES[0]);
+    assert_eq!(bottom_enemy, ENEMY_ROW_STYLES[2]);
+    assert_eq!(player, PLAYER_STYLE);
+}
+
+#[test]
+fn monochrome_fallback_drops_colors() {
+    let mut game_state = create_world();
+    game_state.main_menu.screen = Screen::Game;
+    let mut backend = MemoryBackend::new(COLUMNS, ROWS);
+    backend.color = false;
+    let mut renderer = Render::new(backend).unwrap();
+
+    renderer.render(&mut game_state).unwrap();
+
+    assert!(!renderer.color);
+    let player = renderer.backend.style(2 + 55, 42 - 7).unwrap();
+    assert_eq!(player, Style::default());
+    assert_snapshot("first_game_frame", &renderer.backend);
+}
+
+#[test]
+fn mouse_picks_menu_entries_and_field_columns() {
+    let mut game_state = create_world();
+    let renderer = renderer(COLUMNS, ROWS);
+
+    // Main menu entries are 15 columns wide, on row 42 - 20
+    let menu_left = main_menu_left(2);
+    assert_eq!(
+        renderer.menu_entry_at(&game_state, menu_left + 15, 22),
+        Some(MenuEntry::Item(MenuItem::JoinGame))
+    );
+    assert_eq!(
+        renderer.menu_entry_at(&game_state, menu_left + 15, 23),
+        None
+    );
+    assert_eq!(renderer.menu_entry_at(&game_state, menu_left - 1, 22), None);
+
+    game_state.main_menu.screen = Screen::Hosting;
+    assert_eq!(
+        renderer.menu_entry_at(&game_state, 2 + 35, 42 - 17),
+        Some(MenuEntry::Back)
+    );
+    game_state.networking.connected = true;
+    assert_eq!(
+        renderer.menu_entry_at(&game_state, 2 + 35, 42 - 17),
+        Some(MenuEntry::Start)
+    );
+
+    assert_eq!(renderer.field_column(2 + 40, 42 - 7), Some(40));
+    assert_eq!(renderer.field_column(2, 42 - 7), None);
+    assert_eq!(renderer.field_column(2 + 40, 42), None);
+}
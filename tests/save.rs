@@ -0,0 +1,48 @@
+use invaderse::{Difficulty, SaveGame, Settings, WaveSet, resume_world};
+
+fn save() -> SaveGame {
+    SaveGame {
+        difficulty: Difficulty::Hard,
+        wave: 4,
+        boss_wave: false,
+        score: 1250,
+        player_lives: 2,
+        local_coop: true,
+        custom_lives: false,
+    }
+}
+
+#[test]
+fn saved_game_round_trips() {
+    let save = save();
+    assert_eq!(SaveGame::parse(&save.to_toml()).unwrap(), save);
+    assert!(SaveGame::parse("wave = 3").is_err());
+}
+
+#[test]
+fn continuing_starts_the_saved_wave_over() {
+    let game_state = resume_world(900, WaveSet::builtin(), Settings::default(), &save());
+
+    assert_eq!(game_state.wave, 4);
+    assert_eq!(game_state.score, 1250);
+    assert_eq!(game_state.player_lives, 2);
+    assert_eq!(game_state.high_score, 900);
+    assert_eq!(game_state.difficulty, Difficulty::Hard);
+    assert_eq!(game_state.settings.difficulty, Difficulty::Normal);
+    assert_eq!(game_state.enemy_amount, 0);
+    assert!(!game_state.paused);
+}
+
+#[test]
+fn continued_game_keeps_its_custom_lives() {
+    let save = SaveGame {
+        custom_lives: true,
+        ..save()
+    };
+    let mut game_state = resume_world(900, WaveSet::builtin(), Settings::default(), &save);
+    game_state.score = 2000;
+    game_state.end_game();
+
+    assert!(!game_state.new_high_score);
+    assert_eq!(game_state.high_scores.hard, 0);
+}
@@ -0,0 +1,209 @@
+use invaderse::{
+    Difficulty, EnemyKind, GameState, HighScores, ProjectileSpawner, Settings, SettingsItem,
+    Velocity, WaveSet, restart_world,
+};
+
+/// Speed and fire probability the formation's octopuses were spawned with
+fn octopus_pace(game_state: &GameState) -> (f32, f64) {
+    game_state
+        .world
+        .query::<(&EnemyKind, &Velocity, &ProjectileSpawner)>()
+        .iter()
+        .find(|(_, (kind, _, _))| **kind == EnemyKind::Octopus)
+        .map(|(_, (_, velocity, spawner))| (velocity.speed, spawner.probability))
+        .unwrap()
+}
+
+#[test]
+fn options_left_out_keep_their_defaults() {
+    let settings = Settings::parse(
+        r#"
+        difficulty = "hard"
+        starting_lives = 5
+        join_address = "10.0.0.2:4000"
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(settings.difficulty, Difficulty::Hard);
+    assert_eq!(settings.starting_lives, 5);
+    assert_eq!(settings.join_address.to_string(), "10.0.0.2:4000");
+    assert_eq!(settings.tick_rate, Settings::default().tick_rate);
+    assert!(settings.color);
+}
+
+#[test]
+fn rejects_out_of_range_options() {
+    let errors = Settings::parse(
+        r#"
+        starting_lives = 0
+        tick_rate = 0
+        "#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        errors,
+        vec![
+            "starting_lives must be between 1 and 9",
+            "tick_rate must be greater than 0",
+        ]
+    );
+}
+
+#[test]
+fn changed_settings_save_and_load_back() {
+    let mut settings = Settings::default();
+    settings.cycle(SettingsItem::Difficulty);
+    settings.cycle(SettingsItem::TickRate);
+    settings.cycle(SettingsItem::Color);
+    settings.set_text(SettingsItem::HostPort, "4000").unwrap();
+    assert_eq!(
+        settings.set_text(SettingsItem::JoinAddress, "localhost"),
+        Err("'localhost' is not an address like 127.0.0.1:23471".to_string())
+    );
+
+    assert_eq!(settings.difficulty, Difficulty::Hard);
+    assert_eq!(settings.tick_rate, 120);
+    assert_eq!(Settings::parse(&settings.to_toml()).unwrap(), settings);
+}
+
+#[test]
+fn starting_lives_wrap_around() {
+    let mut settings = Settings {
+        starting_lives: 9,
+        ..Settings::default()
+    };
+    settings.cycle(SettingsItem::StartingLives);
+    assert_eq!(settings.starting_lives, 1);
+}
+
+#[test]
+fn restart_applies_the_settings() {
+    let normal = restart_world(0, WaveSet::builtin(), Settings::default());
+    let easy = restart_world(
+        120,
+        WaveSet::builtin(),
+        Settings {
+            difficulty: Difficulty::Easy,
+            starting_lives: 5,
+            ..Settings::default()
+        },
+    );
+
+    assert_eq!(easy.player_lives, 5);
+    assert_eq!(easy.high_score, 120);
+    let (easy_speed, easy_fire) = octopus_pace(&easy);
+    let (normal_speed, normal_fire) = octopus_pace(&normal);
+    assert!(easy_speed < normal_speed);
+    assert!(easy_fire < normal_fire);
+}
+
+#[test]
+fn picking_a_difficulty_switches_to_its_lives() {
+    let mut settings = Settings::default();
+    settings.cycle(SettingsItem::Difficulty);
+    settings.cycle(SettingsItem::Difficulty);
+    assert_eq!(settings.difficulty, Difficulty::Insane);
+    assert_eq!(settings.starting_lives, 1);
+
+    settings.cycle(SettingsItem::Difficulty);
+    assert_eq!(settings.difficulty, Difficulty::Easy);
+    assert_eq!(settings.starting_lives, 5);
+}
+
+#[test]
+fn insane_cannon_is_slower_and_keeps_its_difficulty() {
+    let mut settings = Settings {
+        difficulty: Difficulty::Insane,
+        ..Settings::default()
+    };
+    let mut game_state = restart_world(0, WaveSet::builtin(), settings.clone());
+    // Changes made while the game runs wait for the next one
+    settings.difficulty = Difficulty::Easy;
+    game_state.settings = settings;
+
+    let speed = game_state
+        .world
+        .get::<&Velocity>(game_state.player_entity)
+        .unwrap()
+        .speed;
+    assert!(speed < Difficulty::Normal.preset().player_speed);
+    assert_eq!(game_state.difficulty, Difficulty::Insane);
+}
+
+#[test]
+fn high_scores_are_recorded_per_difficulty() {
+    let mut game_state = restart_world(0, WaveSet::builtin(), Settings::default());
+    game_state.high_scores.hard = 900;
+    game_state.score = 300;
+    game_state.end_game();
+
+    assert!(game_state.new_high_score);
+    assert_eq!(game_state.high_scores.normal, 300);
+    assert_eq!(game_state.high_scores.hard, 900);
+
+    let mut high_scores = game_state.high_scores;
+    assert!(!high_scores.record(Difficulty::Hard, 800));
+    assert_eq!(
+        HighScores::parse(&high_scores.to_toml()).unwrap(),
+        high_scores
+    );
+}
+
+#[test]
+fn games_with_custom_lives_set_no_high_score() {
+    let mut game_state = restart_world(
+        100,
+        WaveSet::builtin(),
+        Settings {
+            starting_lives: 9,
+            ..Settings::default()
+        },
+    );
+    assert_eq!(
+        game_state.settings.value(SettingsItem::StartingLives),
+        "9 (no high scores)"
+    );
+    game_state.score = 300;
+    game_state.end_game();
+
+    assert!(game_state.game_over);
+    assert!(!game_state.new_high_score);
+    assert_eq!(game_state.high_score, 100);
+    assert_eq!(game_state.high_scores.normal, 0);
+
+    // Lives matching the preset count as usual
+    let mut game_state = restart_world(
+        0,
+        WaveSet::builtin(),
+        Settings {
+            difficulty: Difficulty::Easy,
+            starting_lives: 5,
+            ..Settings::default()
+        },
+    );
+    game_state.score = 300;
+    game_state.end_game();
+    assert_eq!(game_state.high_scores.easy, 300);
+}
+
+#[test]
+fn harder_difficulties_spawn_faster_enemies() {
+    let paces: Vec<(f32, f64)> = Difficulty::ALL
+        .into_iter()
+        .map(|difficulty| {
+            let settings = Settings {
+                difficulty,
+                starting_lives: difficulty.preset().starting_lives,
+                ..Settings::default()
+            };
+            octopus_pace(&restart_world(0, WaveSet::builtin(), settings))
+        })
+        .collect();
+
+    for pair in paces.windows(2) {
+        assert!(pair[0].0 < pair[1].0, "speeds {paces:?}");
+        assert!(pair[0].1 < pair[1].1, "fire probabilities {paces:?}");
+    }
+}
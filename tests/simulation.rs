@@ -0,0 +1,574 @@
+use hecs::Entity;
+use invaderse::{
+    BunkerCell, Collides, Direction, ENEMY_SHOT_BOUNDS, Enemy, EnemyKind, EnemyProjectile,
+    EntityKind, FloatingText, Health, PLAYER_SHOT_BOUNDS, POWER_UP_BOUNDS, Pierced, Player,
+    PlayerProjectile, Position, PowerUp, PowerUpKind, PowerUps, Simulation, Snapshot, TickInput,
+    Ufo, Velocity, WaveSet,
+};
+
+fn player_x(snapshot: &Snapshot) -> u16 {
+    snapshot
+        .entities
+        .iter()
+        .find(|e| e.kind == EntityKind::Player)
+        .unwrap()
+        .x
+}
+
+fn bunker_hp(sim: &Simulation, x: u16, y: u16) -> Option<u8> {
+    sim.game_state()
+        .world
+        .query::<(&Position, &Health)>()
+        .with::<&BunkerCell>()
+        .iter()
+        .find(|(_, (pos, _))| pos.x == x && pos.y == y)
+        .map(|(_, (_, health))| health.hp)
+}
+
+#[test]
+fn bunker_cell_absorbs_player_shot() {
+    let mut sim = Simulation::new(60, 7);
+    let left = TickInput {
+        move_left: true,
+        ..TickInput::default()
+    };
+
+    // Line the cannon up so its shot passes the bunker arch and hits the middle row
+    let mut snapshot = sim.step(TickInput::default()).unwrap();
+    while player_x(&snapshot) > 43 {
+        snapshot = sim.step(left).unwrap();
+    }
+    assert_eq!(player_x(&snapshot), 43);
+    assert_eq!(bunker_hp(&sim, 45, 12), Some(3));
+
+    sim.step(TickInput {
+        shoot: true,
+        ..TickInput::default()
+    })
+    .unwrap();
+    for _ in 0..30 {
+        sim.step(TickInput::default()).unwrap();
+    }
+
+    assert_eq!(bunker_hp(&sim, 45, 12), Some(2));
+    assert!(!sim.game_state().player_projectile_exists);
+}
+
+#[test]
+fn ufo_awards_bonus_and_shows_score() {
+    let mut sim = Simulation::new(60, 7);
+    sim.game_state_mut().ufo_timer = 0.0;
+    let snapshot = sim.step(TickInput::default()).unwrap();
+    let ufo = *snapshot
+        .entities
+        .iter()
+        .find(|e| e.kind == EntityKind::Ufo)
+        .unwrap();
+    assert_eq!(ufo.y, 39);
+
+    // Park the saucer and fire straight into it from just below
+    let world = &mut sim.game_state_mut().world;
+    for (_, vel) in world.query_mut::<&mut Velocity>().with::<&Ufo>() {
+        vel.direction = Direction::None;
+    }
+    world.spawn((
+        PlayerProjectile,
+        Position {
+            x: ufo.x + 2,
+            y: 38,
+        },
+        Velocity {
+            speed: 40.0,
+            move_accumulator: 0.0,
+            direction: Direction::None,
+        },
+        PLAYER_SHOT_BOUNDS,
+        Collides::DestroySelf,
+    ));
+    for _ in 0..5 {
+        sim.step(TickInput::default()).unwrap();
+    }
+
+    let snapshot = sim.snapshot();
+    assert!(snapshot.entities.iter().all(|e| e.kind != EntityKind::Ufo));
+    assert!([50, 100, 150, 300].contains(&snapshot.score));
+    let popup = sim
+        .game_state()
+        .world
+        .query::<&FloatingText>()
+        .iter()
+        .map(|(_, text)| text.text.clone())
+        .next();
+    assert_eq!(popup, Some(snapshot.score.to_string()));
+}
+
+fn fire_from(sim: &mut Simulation, x: u16, y: u16) {
+    sim.game_state_mut().world.spawn((
+        PlayerProjectile,
+        Position { x, y },
+        Velocity {
+            speed: 40.0,
+            move_accumulator: 0.0,
+            direction: Direction::None,
+        },
+        PLAYER_SHOT_BOUNDS,
+        Collides::DestroySelf,
+    ));
+    for _ in 0..5 {
+        sim.step(TickInput::default()).unwrap();
+    }
+}
+
+#[test]
+fn armored_enemy_takes_two_hits() {
+    let mut sim = Simulation::new(60, 7);
+    sim.step(TickInput::default()).unwrap();
+
+    let crab = sim
+        .game_state()
+        .world
+        .query::<(&Position, &EnemyKind)>()
+        .iter()
+        .filter(|(_, (_, kind))| **kind == EnemyKind::Crab)
+        .map(|(id, (pos, _))| (id, *pos))
+        .min_by_key(|(_, pos)| pos.x)
+        .unwrap();
+    let (crab_id, crab_pos) = crab;
+
+    fire_from(&mut sim, crab_pos.x + 2, crab_pos.y - 1);
+    let hp = sim
+        .game_state()
+        .world
+        .get::<&Health>(crab_id)
+        .map(|health| health.hp)
+        .unwrap();
+    assert_eq!(hp, 1);
+    assert_eq!(sim.snapshot().score, 0);
+
+    let crab_pos = *sim.game_state().world.get::<&Position>(crab_id).unwrap();
+    fire_from(&mut sim, crab_pos.x + 2, crab_pos.y - 1);
+    assert!(!sim.game_state().world.contains(crab_id));
+    assert_eq!(sim.snapshot().score, EnemyKind::Crab.stats().points);
+}
+
+#[test]
+fn every_overlapping_pair_is_resolved_each_tick() {
+    let mut sim = Simulation::new(60, 7);
+    sim.step(TickInput::default()).unwrap();
+
+    // Two shots right under two different front row invaders
+    let targets: Vec<Position> = sim
+        .game_state()
+        .world
+        .query::<(&Position, &EnemyKind)>()
+        .iter()
+        .filter(|(_, (_, kind))| **kind == EnemyKind::Octopus)
+        .map(|(_, (pos, _))| *pos)
+        .take(2)
+        .collect();
+    for target in &targets {
+        sim.game_state_mut().world.spawn((
+            PlayerProjectile,
+            Position {
+                x: target.x + 2,
+                y: target.y - 1,
+            },
+            PLAYER_SHOT_BOUNDS,
+            Collides::DestroySelf,
+        ));
+    }
+    sim.step(TickInput::default()).unwrap();
+
+    let snapshot = sim.snapshot();
+    assert_eq!(snapshot.enemy_amount, 28);
+    assert_eq!(snapshot.score, 2 * EnemyKind::Octopus.stats().points);
+    assert!(
+        snapshot
+            .entities
+            .iter()
+            .all(|e| e.kind != EntityKind::PlayerProjectile)
+    );
+}
+
+#[test]
+fn caught_spread_shot_fires_three_projectiles() {
+    let mut sim = Simulation::new(60, 7);
+    let snapshot = sim.step(TickInput::default()).unwrap();
+    let x = player_x(&snapshot);
+
+    sim.game_state_mut().world.spawn((
+        PowerUp {
+            kind: PowerUpKind::SpreadShot,
+        },
+        Position { x: x + 2, y: 7 },
+        POWER_UP_BOUNDS,
+        Collides::DestroySelf,
+    ));
+    sim.step(TickInput::default()).unwrap();
+
+    let player = sim.game_state().player_entity;
+    let spread = sim
+        .game_state()
+        .world
+        .get::<&PowerUps>(player)
+        .unwrap()
+        .spread_shot;
+    assert!(spread > 0.0);
+
+    let snapshot = sim
+        .step(TickInput {
+            shoot: true,
+            ..TickInput::default()
+        })
+        .unwrap();
+    let shots = snapshot
+        .entities
+        .iter()
+        .filter(|e| e.kind == EntityKind::PlayerProjectile)
+        .count();
+    assert_eq!(shots, 3);
+}
+
+#[test]
+fn killed_enemy_explodes_and_the_explosion_cleans_up() {
+    let mut sim = Simulation::new(60, 7);
+    sim.step(TickInput::default()).unwrap();
+
+    let target = sim
+        .game_state()
+        .world
+        .query::<(&Position, &EnemyKind)>()
+        .iter()
+        .find(|(_, (_, kind))| **kind == EnemyKind::Octopus)
+        .map(|(_, (pos, _))| *pos)
+        .unwrap();
+    sim.game_state_mut().world.spawn((
+        PlayerProjectile,
+        Position {
+            x: target.x + 2,
+            y: target.y - 1,
+        },
+        PLAYER_SHOT_BOUNDS,
+        Collides::DestroySelf,
+    ));
+
+    let snapshot = sim.step(TickInput::default()).unwrap();
+    let explosion = snapshot
+        .entities
+        .iter()
+        .find(|e| e.kind == EntityKind::Effect)
+        .unwrap();
+    assert_eq!((explosion.x, explosion.y), (target.x, target.y));
+
+    // Three frames of a tenth of a second each
+    for _ in 0..20 {
+        sim.step(TickInput::default()).unwrap();
+    }
+    assert!(
+        sim.snapshot()
+            .entities
+            .iter()
+            .all(|e| e.kind != EntityKind::Effect)
+    );
+}
+
+#[test]
+fn colliding_shots_destroy_each_other() {
+    let mut sim = Simulation::new(60, 7);
+    sim.step(TickInput::default()).unwrap();
+
+    // An enemy shot is drawn one row below the player shot it meets
+    let world = &mut sim.game_state_mut().world;
+    world.spawn((
+        EnemyProjectile,
+        Position { x: 30, y: 21 },
+        ENEMY_SHOT_BOUNDS,
+        Collides::DestroySelf,
+    ));
+    world.spawn((
+        PlayerProjectile,
+        Position { x: 30, y: 20 },
+        PLAYER_SHOT_BOUNDS,
+        Collides::DestroySelf,
+    ));
+    let snapshot = sim.step(TickInput::default()).unwrap();
+
+    assert!(snapshot.entities.iter().all(|e| !matches!(
+        e.kind,
+        EntityKind::PlayerProjectile | EntityKind::EnemyProjectile
+    )));
+    assert!(
+        snapshot
+            .entities
+            .iter()
+            .any(|e| e.kind == EntityKind::Effect && (e.x, e.y) == (30, 20))
+    );
+}
+
+#[test]
+fn cannon_follows_the_mouse_and_stops_under_it() {
+    let mut sim = Simulation::new(20, 7);
+    let follow = |x| TickInput {
+        mouse_target: Some(x),
+        ..TickInput::default()
+    };
+
+    // At 20 ticks per second the cannon moves three cells per step
+    let start = player_x(&sim.step(TickInput::default()).unwrap());
+    let mut snapshot = sim.step(follow(start + 10)).unwrap();
+    assert!(player_x(&snapshot) > start);
+    for _ in 0..10 {
+        snapshot = sim.step(follow(start + 10)).unwrap();
+    }
+    assert_eq!(player_x(&snapshot), start + 10);
+
+    // Targets past the walls stop at the wall
+    for _ in 0..60 {
+        snapshot = sim.step(follow(0)).unwrap();
+    }
+    assert_eq!(player_x(&snapshot), 2);
+}
+
+#[test]
+fn local_coop_drives_the_coplayer_without_networking() {
+    let mut sim = Simulation::new(60, 7);
+    sim.game_state_mut().local_coop = true;
+
+    let coplayer_x = |snapshot: &Snapshot| {
+        snapshot
+            .entities
+            .iter()
+            .find(|e| e.kind == EntityKind::CoPlayer)
+            .map(|e| e.x)
+    };
+    let start = coplayer_x(&sim.step(TickInput::default()).unwrap()).unwrap();
+
+    let handler = &mut sim.game_state_mut().coplayer_handler;
+    handler.move_right = true;
+    handler.shoot_held = true;
+    let mut snapshot = sim.step(TickInput::default()).unwrap();
+    for _ in 0..10 {
+        snapshot = sim.step(TickInput::default()).unwrap();
+    }
+
+    assert!(coplayer_x(&snapshot).unwrap() > start);
+    assert!(sim.game_state().coplayer_handler.projectile_exists);
+    assert!(
+        snapshot
+            .entities
+            .iter()
+            .any(|e| e.kind == EntityKind::PlayerProjectile)
+    );
+    assert!(sim.game_state().networking.outbox.is_empty());
+}
+
+#[test]
+fn player_one_shooting_does_not_fire_the_local_coplayer() {
+    let mut sim = Simulation::new(60, 7);
+    sim.game_state_mut().local_coop = true;
+    sim.step(TickInput::default()).unwrap();
+
+    sim.game_state_mut().press_shoot();
+    let snapshot = sim
+        .step(TickInput {
+            shoot: true,
+            ..TickInput::default()
+        })
+        .unwrap();
+
+    let shots = snapshot
+        .entities
+        .iter()
+        .filter(|e| e.kind == EntityKind::PlayerProjectile)
+        .count();
+    assert_eq!(shots, 1);
+    assert!(!sim.game_state().coplayer_handler.projectile_exists);
+}
+
+#[test]
+fn formation_keeps_its_spacing_when_it_bounces_off_the_wall() {
+    let mut sim = Simulation::new(60, 7);
+    let columns = |sim: &Simulation| {
+        let mut rows: Vec<(u16, Vec<u16>)> = Vec::new();
+        for (_, pos) in sim
+            .game_state()
+            .world
+            .query::<&Position>()
+            .with::<&Enemy>()
+            .iter()
+        {
+            match rows.iter_mut().find(|(y, _)| *y == pos.y) {
+                Some((_, xs)) => xs.push(pos.x),
+                None => rows.push((pos.y, vec![pos.x])),
+            }
+        }
+        rows.sort();
+        for (_, xs) in &mut rows {
+            xs.sort();
+        }
+        rows
+    };
+    let before = columns(&sim);
+    // Several cells a tick, so the edge would overshoot the wall
+    for (_, vel) in sim
+        .game_state_mut()
+        .world
+        .query_mut::<&mut Velocity>()
+        .with::<&Enemy>()
+    {
+        vel.speed = 400.0;
+    }
+
+    for _ in 0..600 {
+        sim.step(TickInput::default()).unwrap();
+        if matches!(sim.game_state().enemy_direction, Direction::Left) {
+            break;
+        }
+    }
+    assert!(matches!(sim.game_state().enemy_direction, Direction::Left));
+
+    let after = columns(&sim);
+    assert_eq!(after.len(), before.len());
+    let shift = after[0].1[0] - before[0].1[0];
+    for ((y_before, xs_before), (y_after, xs_after)) in before.iter().zip(&after) {
+        assert_eq!(*y_after, y_before - 1, "the formation stepped down once");
+        let shifted: Vec<u16> = xs_before.iter().map(|x| x + shift).collect();
+        assert_eq!(xs_after, &shifted, "every invader moved the same distance");
+    }
+    assert_eq!(
+        after[0].1.last(),
+        Some(&113),
+        "the right edge stopped at the wall"
+    );
+}
+
+fn leftmost_crab(sim: &Simulation) -> (Entity, Position) {
+    sim.game_state()
+        .world
+        .query::<(&Position, &EnemyKind)>()
+        .iter()
+        .filter(|(_, (_, kind))| **kind == EnemyKind::Crab)
+        .map(|(id, (pos, _))| (id, *pos))
+        .min_by_key(|(_, pos)| pos.x)
+        .unwrap()
+}
+
+#[test]
+fn invader_ramming_the_cannon_costs_one_life_and_scores_nothing() {
+    let mut sim = Simulation::new(60, 7);
+    let before = sim.step(TickInput::default()).unwrap();
+
+    let cannon = sim
+        .game_state()
+        .world
+        .query::<&Position>()
+        .with::<&Player>()
+        .iter()
+        .map(|(_, pos)| *pos)
+        .next()
+        .unwrap();
+    let (crab, _) = leftmost_crab(&sim);
+    *sim.game_state_mut()
+        .world
+        .get::<&mut Position>(crab)
+        .unwrap() = cannon;
+
+    for _ in 0..10 {
+        sim.step(TickInput::default()).unwrap();
+    }
+    let after = sim.snapshot();
+    assert!(!sim.game_state().world.contains(crab));
+    assert_eq!(after.player_lives, before.player_lives - 1);
+    assert_eq!(after.enemy_amount, before.enemy_amount - 1);
+    assert_eq!(after.score, 0);
+}
+
+#[test]
+fn piercing_shot_hits_each_invader_once() {
+    let mut sim = Simulation::new(60, 7);
+    sim.step(TickInput::default()).unwrap();
+
+    let (crab, pos) = leftmost_crab(&sim);
+    sim.game_state_mut().world.spawn((
+        PlayerProjectile,
+        Position {
+            x: pos.x + 2,
+            y: pos.y - 1,
+        },
+        Velocity {
+            speed: 40.0,
+            move_accumulator: 0.0,
+            direction: Direction::None,
+        },
+        PLAYER_SHOT_BOUNDS,
+        Collides::Pierce,
+        Pierced::default(),
+    ));
+    for _ in 0..10 {
+        sim.step(TickInput::default()).unwrap();
+    }
+
+    // The shot spent several ticks inside the crab but only took one of its two points
+    let hp = sim.game_state().world.get::<&Health>(crab).unwrap().hp;
+    assert_eq!(hp, 1);
+}
+
+/// Moves back and forth across the field and keeps firing
+fn scripted_input(tick: u32) -> TickInput {
+    TickInput {
+        move_left: tick % 240 < 120,
+        move_right: tick % 240 >= 120,
+        shoot: tick.is_multiple_of(20),
+        mouse_target: None,
+    }
+}
+
+#[test]
+fn same_seed_and_inputs_give_the_same_game() {
+    let mut first = Simulation::new(60, 11);
+    let mut second = Simulation::new(60, 11);
+    let mut other_seed = Simulation::new(60, 12);
+
+    let mut diverged = false;
+    for tick in 0..1200 {
+        let input = scripted_input(tick);
+        let snapshot = first.step(input).unwrap();
+        assert_eq!(snapshot, second.step(input).unwrap(), "tick {tick}");
+        diverged |= snapshot != other_seed.step(input).unwrap();
+    }
+    // Enemy fire follows the seed
+    assert!(diverged);
+}
+
+#[test]
+fn with_waves_plays_the_given_formation() {
+    let waves = WaveSet::parse(
+        r#"
+        [[wave]]
+        formation = ["S.C", "OOO"]
+        "#,
+    )
+    .unwrap();
+    let mut sim = Simulation::with_waves(60, 7, waves);
+    let snapshot = sim.step(TickInput::default()).unwrap();
+
+    assert_eq!(snapshot.enemy_amount, 5);
+    let mut kinds: Vec<EnemyKind> = sim
+        .game_state()
+        .world
+        .query::<&EnemyKind>()
+        .iter()
+        .map(|(_, kind)| *kind)
+        .collect();
+    kinds.sort_by_key(|kind| kind.stats().points);
+    assert_eq!(
+        kinds,
+        [
+            EnemyKind::Octopus,
+            EnemyKind::Octopus,
+            EnemyKind::Octopus,
+            EnemyKind::Crab,
+            EnemyKind::Squid
+        ]
+    );
+}
@@ -0,0 +1,73 @@
+use invaderse::{
+    BOSS_PARTS, EffectKind, EnemyKind, PLAYER_SHOT_SPRITE, PLAYER_SPRITE, PowerUpKind, Settings,
+    Sprite, SpriteSet, UFO_SPRITE, bunker_sprite,
+};
+
+/// Every sprite with the width of the entity drawing it
+fn sprites() -> Vec<(Sprite, u16)> {
+    let mut sprites = vec![
+        (PLAYER_SPRITE.0, 5),
+        (PLAYER_SPRITE.1, 5),
+        (PLAYER_SHOT_SPRITE, 1),
+        (UFO_SPRITE, 6),
+    ];
+    for kind in [EnemyKind::Squid, EnemyKind::Crab, EnemyKind::Octopus] {
+        for (top, bottom) in kind.stats().frames {
+            sprites.extend([(top, 5), (bottom, 5)]);
+        }
+    }
+    for kind in [
+        EffectKind::EnemyExplosion,
+        EffectKind::PlayerExplosion,
+        EffectKind::ShotBurst,
+    ] {
+        for &(top, bottom) in kind.frames() {
+            sprites.extend([(top, kind.width()), (bottom, kind.width())]);
+        }
+    }
+    for (_, _, top, bottom, _) in BOSS_PARTS {
+        sprites.extend([(top, 5), (bottom, 5)]);
+    }
+    for hp in 0..=3 {
+        sprites.push((bunker_sprite(hp), 1));
+    }
+    sprites.push((PowerUpKind::Shield.sprite(), 1));
+    sprites
+}
+
+#[test]
+fn every_set_draws_sprites_as_wide_as_their_entities() {
+    for (sprite, width) in sprites() {
+        for set in SpriteSet::ALL {
+            let cells = sprite.glyphs(set).chars().count() as u16;
+            assert!(
+                cells == width || cells == 0,
+                "{set:?} sprite for '{}' is {cells} cells wide, not {width}",
+                sprite.braille
+            );
+        }
+    }
+}
+
+#[test]
+fn ascii_set_is_plain_ascii() {
+    for (sprite, _) in sprites() {
+        assert!(sprite.ascii.is_ascii(), "'{}' is not ASCII", sprite.ascii);
+    }
+}
+
+#[test]
+#[should_panic(expected = "every sprite set must be as wide as the braille sprite")]
+fn sprites_of_different_widths_are_refused() {
+    Sprite::new("⣿⣿", "#", "██");
+}
+
+#[test]
+fn sprite_set_is_picked_by_name() {
+    assert_eq!(SpriteSet::from_name("ascii"), Some(SpriteSet::Ascii));
+    assert_eq!(SpriteSet::from_name("Block"), Some(SpriteSet::Block));
+    assert_eq!(SpriteSet::from_name("emoji"), None);
+
+    let settings = Settings::parse(r#"sprite_set = "block""#).unwrap();
+    assert_eq!(settings.sprite_set, SpriteSet::Block);
+}
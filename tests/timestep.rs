@@ -0,0 +1,45 @@
+use std::time::{Duration, Instant};
+
+use invaderse::FixedTimestep;
+
+/// Timestep synced to `start`, the first call only takes up the microseconds
+/// spent creating it
+fn timestep(tick_rate: u32, start: Instant) -> FixedTimestep {
+    let mut timestep = FixedTimestep::new(tick_rate);
+    assert_eq!(timestep.advance(start), 0);
+    timestep
+}
+
+#[test]
+fn remainder_carries_over_to_the_next_frame() {
+    let start = Instant::now();
+    let mut timestep = timestep(100, start);
+
+    assert_eq!(timestep.advance(start + Duration::from_millis(35)), 3);
+    assert_eq!(timestep.advance(start + Duration::from_millis(50)), 2);
+}
+
+#[test]
+fn a_stall_only_simulates_a_bounded_stretch() {
+    let start = Instant::now();
+    let mut timestep = timestep(100, start);
+
+    assert_eq!(timestep.advance(start + Duration::from_secs(5)), 10);
+    assert_eq!(
+        timestep.advance(start + Duration::from_millis(5015)),
+        1,
+        "frames after the stall run at the normal pace"
+    );
+}
+
+#[test]
+fn high_tick_rates_keep_up_with_the_frame_rate() {
+    let start = Instant::now();
+    let mut timestep = timestep(500, start);
+
+    let steps: u32 = (1..=100)
+        .map(|frame| timestep.advance(start + Duration::from_millis(16 * frame)))
+        .sum();
+    // 1.6 seconds at 500 Hz, give or take the step in flight
+    assert!((799..=800).contains(&steps), "{steps} steps");
+}
@@ -0,0 +1,425 @@
+use invaderse::{
+    Boss, BossPart, Collides, Direction, Diving, EnemyKind, EntityKind, PLAYER_SHOT_BOUNDS,
+    Pierced, PlayerProjectile, Position, ProjectileSpawner, Simulation, TickInput, Velocity,
+    WaveEvent, WaveSet,
+};
+
+#[test]
+fn builtin_waves_are_valid() {
+    let waves = WaveSet::builtin();
+    assert!(!waves.waves.is_empty());
+    assert_eq!(waves.wave(1).enemy_count(), 30);
+}
+
+#[test]
+fn parses_formation_and_events() {
+    let waves = WaveSet::parse(
+        r#"
+        [[wave]]
+        speed = 2.0
+        events = ["ufo"]
+        formation = ["S.C", "OOO"]
+        "#,
+    )
+    .unwrap();
+
+    let wave = waves.wave(1);
+    assert_eq!(wave.speed, 2.0);
+    assert_eq!(wave.fire_rate, 1.0);
+    assert_eq!(wave.events, vec![WaveEvent::Ufo]);
+    assert_eq!(
+        wave.formation[0],
+        vec![Some(EnemyKind::Squid), None, Some(EnemyKind::Crab)]
+    );
+    assert_eq!(wave.enemy_count(), 5);
+}
+
+#[test]
+fn repeats_of_the_last_wave_are_scaled() {
+    let waves = WaveSet::parse(
+        r#"
+        speed_scale = 2.0
+        fire_scale = 3.0
+        [[wave]]
+        formation = ["O"]
+        "#,
+    )
+    .unwrap();
+
+    let wave = waves.wave(3);
+    assert_eq!(wave.speed, 4.0);
+    assert_eq!(wave.fire_rate, 9.0);
+}
+
+#[test]
+fn difficulty_scaling_multiplies_the_scale_factors() {
+    let waves = WaveSet::parse(
+        r#"
+        speed_scale = 2.0
+        fire_scale = 3.0
+        [[wave]]
+        formation = ["O"]
+        "#,
+    )
+    .unwrap();
+
+    let wave = waves.scaled_wave(2, 0.5);
+    assert_eq!(wave.speed, 1.0);
+    assert_eq!(wave.fire_rate, 1.5);
+    assert_eq!(waves.scaled_wave(1, 0.5).speed, 1.0);
+}
+
+#[test]
+fn reports_every_validation_error() {
+    let errors = WaveSet::parse(
+        r#"
+        [[wave]]
+        speed = 0.0
+        formation = ["SXS"]
+
+        [[wave]]
+        formation = ["...", "OOOOOOOOOOOOOOOOOOOO"]
+        "#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        errors,
+        vec![
+            "wave 1: speed must be greater than 0",
+            "wave 1, row 1: unknown enemy 'X'",
+            "wave 2, row 2: 20 columns, at most 15 fit",
+        ]
+    );
+}
+
+#[test]
+fn rejects_malformed_toml() {
+    let errors = WaveSet::parse("[[wave]]\nformation = 3").unwrap_err();
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn speedup_is_interpolated_between_points() {
+    let waves = WaveSet::parse(
+        r#"
+        speedup = [[1.0, 1.0], [0.5, 2.0], [0.0, 4.0]]
+
+        [[wave]]
+        formation = ["O"]
+        "#,
+    )
+    .unwrap();
+
+    assert_eq!(waves.speedup_at(1.0), 1.0);
+    assert_eq!(waves.speedup_at(0.75), 1.5);
+    assert_eq!(waves.speedup_at(0.5), 2.0);
+    assert_eq!(waves.speedup_at(0.25), 3.0);
+    assert_eq!(waves.speedup_at(0.0), 4.0);
+}
+
+#[test]
+fn rejects_a_speedup_curve_going_the_wrong_way() {
+    let errors = WaveSet::parse(
+        r#"
+        speedup = [[0.5, 2.0], [1.5, 0.0]]
+
+        [[wave]]
+        formation = ["O"]
+        "#,
+    )
+    .unwrap_err();
+
+    assert_eq!(
+        errors,
+        vec![
+            "speedup point 2: share must be between 0 and 1",
+            "speedup point 2: factor must be greater than 0",
+            "speedup point 2: shares must go down from the full formation",
+        ]
+    );
+}
+
+#[test]
+fn last_invader_marches_faster() {
+    let waves = WaveSet::parse(
+        r#"
+        speedup = [[1.0, 1.0], [0.0, 3.0]]
+
+        [[wave]]
+        fire_rate = 0.0
+        formation = ["OO"]
+        "#,
+    )
+    .unwrap();
+    let mut sim = Simulation::with_waves(60, 7, waves);
+    sim.step(TickInput::default()).unwrap();
+    let distance = |sim: &mut Simulation| {
+        let start = sim
+            .snapshot()
+            .entities
+            .iter()
+            .find(|e| e.kind == EntityKind::Enemy)
+            .unwrap()
+            .x;
+        for _ in 0..30 {
+            sim.step(TickInput::default()).unwrap();
+        }
+        sim.snapshot()
+            .entities
+            .iter()
+            .find(|e| e.kind == EntityKind::Enemy)
+            .unwrap()
+            .x
+            - start
+    };
+    let full_formation = distance(&mut sim);
+
+    let enemy = sim
+        .game_state()
+        .world
+        .query::<&EnemyKind>()
+        .iter()
+        .map(|(id, _)| id)
+        .next()
+        .unwrap();
+    sim.game_state_mut().world.despawn(enemy).unwrap();
+    sim.game_state_mut().enemy_amount = 1;
+
+    // Half the formation is gone, so it marches twice as fast
+    assert_eq!(distance(&mut sim), 2 * full_formation);
+}
+
+#[test]
+fn next_wave_starts_after_the_banner() {
+    let mut sim = Simulation::new(60, 7);
+    sim.step(TickInput::default()).unwrap();
+
+    // Clear the formation without scoring through the collision pass
+    let enemies: Vec<_> = sim
+        .game_state()
+        .world
+        .query::<&EnemyKind>()
+        .iter()
+        .map(|(id, _)| id)
+        .collect();
+    for enemy in enemies {
+        sim.game_state_mut().world.despawn(enemy).unwrap();
+    }
+    let state = sim.game_state_mut();
+    state.enemy_amount = 0;
+    state.wave = 2;
+    state.wave_banner_timer = 1.0;
+
+    for _ in 0..55 {
+        sim.step(TickInput::default()).unwrap();
+    }
+    assert_eq!(sim.snapshot().enemy_amount, 0);
+
+    for _ in 0..10 {
+        sim.step(TickInput::default()).unwrap();
+    }
+    let snapshot = sim.snapshot();
+    assert_eq!(snapshot.enemy_amount, 30);
+    assert_eq!(sim.game_state().enemy_speed_multiplier, 1.2);
+    let fire_rate = sim.game_state().enemy_proj_prob_multiplier as f64;
+    for (_, (kind, velocity, spawner)) in sim
+        .game_state()
+        .world
+        .query::<(&EnemyKind, &Velocity, &ProjectileSpawner)>()
+        .iter()
+    {
+        assert_eq!(velocity.speed, 20.0 * 1.2);
+        assert_eq!(
+            spawner.probability,
+            kind.stats().fire_probability * fire_rate
+        );
+    }
+    // The second built-in wave opens with a saucer
+    assert!(snapshot.entities.iter().any(|e| e.kind == EntityKind::Ufo));
+}
+
+#[test]
+fn diver_leaves_formation_and_returns_to_its_slot() {
+    let waves = WaveSet::parse(
+        r#"
+        [[wave]]
+        fire_rate = 0.0
+        divers = 1
+        formation = ["O"]
+        "#,
+    )
+    .unwrap();
+    let mut sim = Simulation::with_waves(60, 3, waves);
+    sim.game_state_mut().dive_timer = 0.0;
+    sim.step(TickInput::default()).unwrap();
+
+    let (enemy, diving) = {
+        let state = sim.game_state_mut();
+        let (enemy, diving) = state
+            .world
+            .query_mut::<&mut Diving>()
+            .into_iter()
+            .next()
+            .unwrap();
+        // Turn back above the bunkers rather than looping through the bottom
+        diving.loop_around = false;
+        (enemy, diving.slot)
+    };
+
+    let mut lowest = diving.y;
+    let mut steps = 0;
+    while sim.game_state().world.satisfies::<&Diving>(enemy).unwrap() {
+        sim.step(TickInput::default()).unwrap();
+        lowest = lowest.min(sim.game_state().world.get::<&Position>(enemy).unwrap().y);
+        steps += 1;
+        assert!(steps < 600, "diver never returned");
+    }
+
+    assert!(lowest <= 20, "diver stayed at y {lowest}");
+    let slot = *sim.game_state().world.get::<&Position>(enemy).unwrap();
+    // The slot kept marching with the formation while the diver was away
+    assert_ne!(slot.x, diving.x);
+    assert_eq!(slot.y, diving.y);
+}
+
+fn boss_parts(sim: &Simulation) -> Vec<(Position, bool)> {
+    sim.game_state()
+        .world
+        .query::<(&Position, &BossPart)>()
+        .iter()
+        .map(|(_, (pos, part))| (*pos, part.weak_point))
+        .collect()
+}
+
+fn shoot_at(sim: &mut Simulation, target: Position) {
+    sim.game_state_mut().world.spawn((
+        PlayerProjectile,
+        Position {
+            x: target.x + 2,
+            y: target.y - 1,
+        },
+        PLAYER_SHOT_BOUNDS,
+        Collides::DestroySelf,
+    ));
+    sim.step(TickInput::default()).unwrap();
+}
+
+fn boss_hp(sim: &Simulation) -> Option<u16> {
+    sim.game_state()
+        .world
+        .query::<&Boss>()
+        .iter()
+        .map(|(_, boss)| boss.hp)
+        .next()
+}
+
+#[test]
+fn boss_takes_damage_only_at_weak_points() {
+    let waves = WaveSet::parse(
+        r#"
+        boss_every = 1
+        boss_hp = 2
+        [[wave]]
+        formation = ["O"]
+        "#,
+    )
+    .unwrap();
+    let mut sim = Simulation::with_waves(60, 7, waves);
+
+    // Clearing the only invader of wave 1 is followed by the boss
+    let enemy = sim
+        .snapshot()
+        .entities
+        .iter()
+        .find(|e| e.kind == EntityKind::Enemy)
+        .copied()
+        .unwrap();
+    shoot_at(
+        &mut sim,
+        Position {
+            x: enemy.x,
+            y: enemy.y,
+        },
+    );
+    assert!(sim.game_state().boss_wave);
+    while boss_hp(&sim).is_none() {
+        sim.step(TickInput::default()).unwrap();
+    }
+    assert_eq!(boss_hp(&sim), Some(2));
+
+    let (armor, _) = boss_parts(&sim)
+        .into_iter()
+        .find(|(pos, weak)| !weak && pos.y < 38)
+        .unwrap();
+    shoot_at(&mut sim, armor);
+    assert_eq!(boss_hp(&sim), Some(2));
+
+    for _ in 0..2 {
+        let (core, _) = boss_parts(&sim)
+            .into_iter()
+            .find(|(_, weak)| *weak)
+            .unwrap();
+        shoot_at(&mut sim, core);
+    }
+    assert_eq!(boss_hp(&sim), None);
+    assert!(boss_parts(&sim).is_empty());
+    let state = sim.game_state();
+    assert!(!state.boss_wave);
+    assert_eq!(state.wave, 2);
+}
+
+#[test]
+fn piercing_shot_hits_the_boss_core_once() {
+    let waves = WaveSet::parse(
+        r#"
+        boss_every = 1
+        boss_hp = 5
+        [[wave]]
+        formation = ["O"]
+        "#,
+    )
+    .unwrap();
+    let mut sim = Simulation::with_waves(60, 7, waves);
+    let enemy = sim
+        .snapshot()
+        .entities
+        .iter()
+        .find(|e| e.kind == EntityKind::Enemy)
+        .copied()
+        .unwrap();
+    shoot_at(
+        &mut sim,
+        Position {
+            x: enemy.x,
+            y: enemy.y,
+        },
+    );
+    while boss_hp(&sim).is_none() {
+        sim.step(TickInput::default()).unwrap();
+    }
+
+    let (core, _) = boss_parts(&sim)
+        .into_iter()
+        .find(|(_, weak)| *weak)
+        .unwrap();
+    sim.game_state_mut().world.spawn((
+        PlayerProjectile,
+        Position {
+            x: core.x + 2,
+            y: core.y - 1,
+        },
+        Velocity {
+            speed: 40.0,
+            move_accumulator: 0.0,
+            direction: Direction::None,
+        },
+        PLAYER_SHOT_BOUNDS,
+        Collides::Pierce,
+        Pierced::default(),
+    ));
+    for _ in 0..6 {
+        sim.step(TickInput::default()).unwrap();
+    }
+    assert_eq!(boss_hp(&sim), Some(4));
+}